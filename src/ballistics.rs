@@ -0,0 +1,370 @@
+use std::cmp;
+use std::ops::RangeInclusive;
+
+// how velocity changes along one axis every step
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Axis {
+    Drag(i32),    /* speed drops by this much toward zero, then stays there */
+    Gravity(i32), /* velocity drops by this much forever */
+}
+
+// steps a shot spends inside the target along one axis
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Velocity {
+    Bounded(i32, usize, usize), /* (vel, start, stop) */
+    Unbounded(i32, usize),      /* (vel, start) */
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Region {
+    pub x_min: i32, pub x_max: i32,
+    pub y_min: i32, pub y_max: i32,
+}
+
+impl Velocity {
+    pub fn velocity(&self) -> i32 {
+        match self {
+            Velocity::Bounded(vel, _, _) | Velocity::Unbounded(vel, _) => *vel,
+        }
+    }
+
+    pub fn overlaps(&self, other: &Self) -> bool {
+        use Velocity::*;
+
+        match self {
+            Bounded(_, lstart, lstop) => match other {
+                Bounded(_, rstart, rstop) => lstop >= rstart && rstop >= lstart,
+                Unbounded(_, rstart) => rstart <= lstop,
+            },
+            Unbounded(_, lstart) => match other {
+                Bounded(_, _, rstop) => lstart <= rstop,
+                Unbounded(_, _) => true,
+            },
+        }
+    }
+}
+
+impl Region {
+    pub fn contains(&self, x: i32, y: i32) -> bool {
+        self.x_min <= x && x <= self.x_max && self.y_min <= y && y <= self.y_max
+    }
+
+    pub fn contains_y_zero(&self) -> bool {
+        self.y_min <= 0 && 0 <= self.y_max
+    }
+}
+
+impl Axis {
+    pub fn new_drag(drag: i32) -> Axis {
+        if drag < 0 { panic!("drag can't be negative"); }
+        Axis::Drag(drag)
+    }
+
+    pub fn new_gravity(gravity: i32) -> Axis {
+        if gravity < 0 { panic!("gravity can't be negative"); }
+        Axis::Gravity(gravity)
+    }
+
+    pub fn step(&self, pos: i32, vel: i32) -> (i32, i32) {
+        match self {
+            Axis::Drag(drag) => (pos + vel, vel.signum() * cmp::max(vel.abs() - drag, 0)),
+            Axis::Gravity(gravity) => (pos + vel, vel - gravity),
+        }
+    }
+
+    // true once the probe is outside min..=max and can never come back
+    pub fn past(&self, pos: i32, vel: i32, min: i32, max: i32) -> bool {
+        match self {
+            Axis::Gravity(gravity) if *gravity > 0 => pos < min && vel <= 0,
+            _ => pos > max && vel >= 0 || pos < min && vel <= 0,
+        }
+    }
+
+    // every launch velocity that can possibly land in min..=max
+    pub fn velocities(&self, min: i32, max: i32) -> RangeInclusive<i32> {
+        match self {
+            // the first step moves by the full velocity and it only slows from there
+            Axis::Drag(_) | Axis::Gravity(0) => cmp::min(min, 0)..=cmp::max(max, 0),
+
+            // on the way back down a shot thrown up at v first drops below its launch
+            // height to at or below -(v/g + 1/2), so anything faster than g times the
+            // furthest edge (twice that for targets above) misses. a target spanning
+            // the launch height can still catch it on the way down, see `hits`
+            Axis::Gravity(gravity) => {
+                let reach = cmp::max(min.abs(), max.abs());
+                cmp::min(min, 0)..=(2 * gravity * reach + gravity)
+            },
+        }
+    }
+
+    // highest position reached along this axis, starting from zero
+    pub fn apex(&self, velocity: i32) -> i64 {
+        match self {
+            Axis::Gravity(gravity) if *gravity > 0 => {
+                let peak = peak_step(velocity as i64, *gravity as i64);
+                cmp::max(0, height(velocity as i64, *gravity as i64, peak))
+            },
+            _ => if velocity > 0 { i64::MAX } else { 0 },
+        }
+    }
+
+    // solves for the steps that land in min..=max rather than stepping through them,
+    // a gravity arc can pass through a target above its launch point twice
+    pub fn windows(&self, velocity: i32, min: i32, max: i32) -> Vec<Velocity> {
+        if velocity < 0 {
+            if let Axis::Drag(_) = self {
+                return self.windows(-velocity, -max, -min).into_iter()
+                    .map(|window| match window {
+                        Velocity::Bounded(_, start, stop) => Velocity::Bounded(velocity, start, stop),
+                        Velocity::Unbounded(_, start) => Velocity::Unbounded(velocity, start),
+                    })
+                    .collect();
+            }
+        }
+
+        let (v, min, max) = (velocity as i64, min as i64, max as i64);
+
+        match *self {
+            Axis::Gravity(0) | Axis::Drag(0) => linear_windows(velocity, min, max),
+            Axis::Drag(drag) => drag_windows(velocity, drag as i64, min, max),
+            Axis::Gravity(gravity) => {
+                let g = gravity as i64;
+                let inside = match steps_at_or_above(v, g, min) {
+                    None => return vec![],
+                    Some(steps) => steps,
+                };
+
+                // steps strictly above the target split the time at or above its floor in two
+                let pieces = match steps_at_or_above(v, g, max + 1) {
+                    None => vec![inside],
+                    Some((over_start, over_stop)) => vec![
+                        (inside.0, over_start - 1),
+                        (over_stop + 1, inside.1),
+                    ],
+                };
+
+                pieces.into_iter()
+                    .filter(|(start, stop)| start <= stop)
+                    .map(|(start, stop)| Velocity::Bounded(velocity, start as usize, stop as usize))
+                    .collect()
+            },
+        }
+    }
+}
+
+// every shot whose windows on both axes overlap lands in the region at the same step
+pub fn hits(x_axis: Axis, y_axis: Axis, region: &Region) -> Vec<(i32, i32)> {
+    let mut x_vels: Vec<Velocity> = Vec::new();
+    let mut y_vels: Vec<Velocity> = Vec::new();
+    let mut on_target: Vec<(i32, i32)> = Vec::new();
+
+    for x in x_axis.velocities(region.x_min, region.x_max) {
+        x_vels.append(&mut x_axis.windows(x, region.x_min, region.x_max));
+    }
+
+    // when x never stops, a shot coming back down through the launch height can land as late
+    // as the last step it's lined up along x
+    let mut y_range = y_axis.velocities(region.y_min, region.y_max);
+    let last_step = x_vels.iter()
+        .filter_map(|vel| match vel { Velocity::Bounded(_, _, stop) => Some(*stop), _ => None })
+        .max();
+    if let (Axis::Gravity(gravity), Some(last_step)) = (y_axis, last_step) {
+        if gravity > 0 && region.contains_y_zero() {
+            let fastest = fastest_return(gravity as i64, region.y_max as i64, last_step as i64);
+            y_range = *y_range.start()..=cmp::max(*y_range.end(), fastest);
+        }
+    }
+
+    for y in y_range {
+        y_vels.append(&mut y_axis.windows(y, region.y_min, region.y_max));
+    }
+
+    for dx in x_vels.iter() {
+        for dy in y_vels.iter() {
+            if dx.overlaps(dy) {
+                on_target.push((dx.velocity(), dy.velocity()));
+            }
+        }
+    }
+
+    on_target.sort();
+    on_target.dedup();
+
+    on_target
+}
+
+// a probe that stops dead above the target and falls back through its launch height
+// can be thrown up arbitrarily hard, so there's no finite set of hits
+pub fn infinitely_many_hits(x_axis: Axis, y_axis: Axis, region: &Region) -> bool {
+    let stops_in_target = x_axis.velocities(region.x_min, region.x_max)
+        .flat_map(|x| x_axis.windows(x, region.x_min, region.x_max))
+        .any(|vel| matches!(vel, Velocity::Unbounded(_, _)));
+
+    stops_in_target && region.contains_y_zero() && matches!(y_axis, Axis::Gravity(g) if g > 0)
+}
+
+// steps the probe until it lands in the region or can't reach it any more
+pub fn trajectory(x_axis: Axis, y_axis: Axis, region: &Region, dx: i32, dy: i32) -> Vec<(i32, i32)> {
+    let (mut x, mut y) = (0, 0);
+    let (mut x_vel, mut y_vel) = (dx, dy);
+    let mut positions = Vec::new();
+
+    loop {
+        (x, x_vel) = x_axis.step(x, x_vel);
+        (y, y_vel) = y_axis.step(y, y_vel);
+        positions.push((x, y));
+
+        if region.contains(x, y)
+            || x_axis.past(x, x_vel, region.x_min, region.x_max)
+            || y_axis.past(y, y_vel, region.y_min, region.y_max) {
+            return positions;
+        }
+    }
+}
+
+// a shot thrown up at v is at n * (v - g * (n - 1) / 2) after n steps, so anything faster
+// than this is still above max (which is at or above the launch height) until after last_step
+fn fastest_return(g: i64, max: i64, last_step: i64) -> i32 {
+    let fastest = g * (last_step - 1) / 2 + max;
+    fastest.min(i32::MAX as i64) as i32
+}
+
+fn linear_windows(velocity: i32, min: i64, max: i64) -> Vec<Velocity> {
+    let v = velocity as i64;
+
+    if v == 0 {
+        return if min <= 0 && 0 <= max { vec![Velocity::Unbounded(velocity, 1)] } else { vec![] };
+    }
+
+    // mirror shots moving down so the target is always ahead
+    let (v, min, max) = if v < 0 { (-v, -max, -min) } else { (v, min, max) };
+    let start = cmp::max(1, div_ceil(min, v));
+    let stop = max.div_euclid(v);
+
+    if start <= stop {
+        vec![Velocity::Bounded(velocity, start as usize, stop as usize)]
+    } else {
+        vec![]
+    }
+}
+
+// velocity is never negative here, shots to the left are mirrored by the caller
+fn drag_windows(velocity: i32, drag: i64, min: i64, max: i64) -> Vec<Velocity> {
+    let v = velocity as i64;
+
+    if v == 0 {
+        return if min <= 0 && 0 <= max { vec![Velocity::Unbounded(velocity, 1)] } else { vec![] };
+    }
+
+    // position climbs the same arc as gravity would until the probe stops at step `moving`
+    let moving = div_ceil(v, drag);
+    let resting = height(v, drag, moving);
+
+    let start = match steps_at_or_above(v, drag, min) {
+        Some((start, _)) if start <= moving => start,
+        _ => return vec![],
+    };
+
+    if height(v, drag, start) > max {
+        return vec![];
+    }
+
+    if resting <= max {
+        vec![Velocity::Unbounded(velocity, start as usize)]
+    } else {
+        let (over, _) = steps_at_or_above(v, drag, max + 1).unwrap();
+        vec![Velocity::Bounded(velocity, start as usize, (over - 1) as usize)]
+    }
+}
+
+// position after n steps of a shot at v that slows by g every step
+fn height(v: i64, g: i64, n: i64) -> i64 {
+    n * v - g * n * (n - 1) / 2
+}
+
+// the first step (n >= 1) where the arc is highest
+fn peak_step(v: i64, g: i64) -> i64 {
+    let guess = (v as f64 / g as f64 + 0.5).round() as i64;
+
+    [guess - 1, guess, guess + 1].into_iter()
+        .map(|n| cmp::max(n, 1))
+        .max_by_key(|&n| (height(v, g, n), -n))
+        .unwrap()
+}
+
+// steps n >= 1 where the arc is at or above `level`, solved with the quadratic formula
+// and nudged onto the exact integer bounds. the arc is concave so they're contiguous
+fn steps_at_or_above(v: i64, g: i64, level: i64) -> Option<(i64, i64)> {
+    let peak = peak_step(v, g);
+    if height(v, g, peak) < level {
+        return None;
+    }
+
+    let b = v as f64 + g as f64 / 2.0;
+    let root = (b * b - 2.0 * g as f64 * level as f64).max(0.0).sqrt();
+    let reaches = |n: i64| height(v, g, n) >= level;
+
+    let mut start = ((b - root) / g as f64).ceil().clamp(1.0, peak as f64) as i64;
+    while start > 1 && reaches(start - 1) { start -= 1; }
+    while !reaches(start) { start += 1; }
+
+    let mut stop = cmp::max(peak, ((b + root) / g as f64).floor() as i64);
+    while reaches(stop + 1) { stop += 1; }
+    while !reaches(stop) { stop -= 1; }
+
+    Some((start, stop))
+}
+
+fn div_ceil(numerator: i64, denominator: i64) -> i64 {
+    -((-numerator).div_euclid(denominator))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // every launch in a box wide enough to hold all the hits, stepped until it lands or misses
+    fn simulated_hits(x_axis: Axis, y_axis: Axis, region: &Region) -> Vec<(i32, i32)> {
+        let mut hits = Vec::new();
+
+        for dx in -50..=50 {
+            for dy in -50..=200 {
+                let (x, y) = *trajectory(x_axis, y_axis, region, dx, dy).last().unwrap();
+                if region.contains(x, y) {
+                    hits.push((dx, dy));
+                }
+            }
+        }
+
+        hits
+    }
+
+    #[test]
+    fn shots_that_never_stop_can_come_back_down_through_a_target_at_launch_height() {
+        let (x_axis, y_axis) = (Axis::new_drag(0), Axis::new_gravity(2));
+        let region = Region { x_min: 38, x_max: 39, y_min: -1, y_max: 7 };
+        let on_target = hits(x_axis, y_axis, &region);
+
+        assert!(on_target.contains(&(1, 37)) && on_target.contains(&(1, 38)));
+        assert_eq!(on_target, simulated_hits(x_axis, y_axis, &region));
+    }
+
+    #[test]
+    fn hits_match_simulation_with_drag_and_gravity() {
+        let regions = [
+            Region { x_min: 20, x_max: 30, y_min: -10, y_max: -5 },
+            Region { x_min: -38, x_max: -20, y_min: -2, y_max: 8 },
+            Region { x_min: 2, x_max: 37, y_min: -6, y_max: 7 },
+            Region { x_min: 5, x_max: 12, y_min: 3, y_max: 9 },
+        ];
+
+        for region in regions.iter() {
+            for (drag, gravity) in [(0, 1), (0, 3), (1, 1), (2, 2)] {
+                let (x_axis, y_axis) = (Axis::new_drag(drag), Axis::new_gravity(gravity));
+                if !infinitely_many_hits(x_axis, y_axis, region) {
+                    assert_eq!(hits(x_axis, y_axis, region), simulated_hits(x_axis, y_axis, region), "{:?}", region);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,773 @@
+use std::fmt::{self, Display, Formatter};
+use std::io::{BufReader, Bytes, Read};
+
+const LITERAL_TYPE_ID: u64 = 4;
+const MAX_HEADER_FIELD: u64 = 0b111;
+const MAX_PACKET_COUNT: usize = (1 << 11) - 1;
+const MAX_PACKET_BITS: usize = (1 << 15) - 1;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum LengthType {
+    TotalBits,   /* length type id 0, 15 bit count of sub-packet bits */
+    PacketCount, /* length type id 1, 11 bit count of sub-packets */
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Packet {
+    Literal {
+        version: u64,
+        value: u64,
+    },
+    Operator {
+        version: u64,
+        type_id: u64,
+        length_type: LengthType,
+        packets: Vec<Packet>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DecodeError {
+    OutOfBits { offset: usize },
+    InvalidHex { offset: usize, found: char },
+    LengthMismatch { offset: usize, expected: usize, actual: usize },
+    NonZeroPadding { offset: usize },
+    LiteralOverflow { offset: usize },
+    Read { offset: usize, message: String },
+}
+
+// a packet tree that can't be written without cutting something down to fit its field
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EncodeError {
+    VersionTooLarge { offset: usize, version: u64 },
+    TypeIdTooLarge { offset: usize, type_id: u64 },
+    OperatorAsLiteral { offset: usize },
+    TooManyPackets { offset: usize, count: usize },
+    TooManyBits { offset: usize, bits: usize },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EvalError {
+    Arity { version: u64, type_id: u64, operands: usize },
+    UnknownType { version: u64, type_id: u64 },
+    Overflow { version: u64, type_id: u64 },
+}
+
+// result of a lenient decode, whatever could be read before the first error
+#[derive(Debug)]
+pub struct PartialDecode {
+    pub packet: Option<Packet>,
+    pub errors: Vec<DecodeError>,
+}
+
+// reads bits most significant first directly out of the hex characters of a transmission,
+// one character at a time so only the read buffer is ever held in memory. whitespace
+// (like a trailing newline) between characters is skipped
+pub struct BitReader<R: Read> {
+    hex: Bytes<BufReader<R>>,
+    nibble: u64,
+    nibble_bits: usize,
+    position: usize,
+}
+
+// receives packets bottom up as they're decoded, operators get the outputs of their
+// sub-packets so nothing has to hold the full tree
+pub trait Visitor {
+    type Output;
+
+    fn literal(&mut self, version: u64, value: u64) -> Self::Output;
+    fn operator(&mut self, version: u64, type_id: u64, length_type: LengthType, operands: Vec<Self::Output>) -> Self::Output;
+}
+
+pub struct TreeBuilder;
+pub struct VersionSum;
+pub struct Evaluator;
+
+#[derive(Default)]
+pub struct BitWriter {
+    bits: Vec<bool>,
+}
+
+impl<'a> BitReader<&'a [u8]> {
+    pub fn from_hex(hex: &'a str) -> BitReader<&'a [u8]> {
+        BitReader::new(hex.as_bytes())
+    }
+}
+
+impl<R: Read> BitReader<R> {
+    pub fn new(source: R) -> BitReader<R> {
+        BitReader {
+            hex: BufReader::new(source).bytes(),
+            nibble: 0,
+            nibble_bits: 0,
+            position: 0,
+        }
+    }
+
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    // None at the end of the transmission
+    fn next_nibble(&mut self) -> Result<Option<u64>, DecodeError> {
+        loop {
+            match self.hex.next() {
+                None => return Ok(None),
+                Some(Err(error)) => return Err(DecodeError::Read {
+                    offset: self.position,
+                    message: error.to_string(),
+                }),
+                Some(Ok(ch)) if ch.is_ascii_whitespace() => continue,
+                Some(Ok(ch)) => return hex_value(ch)
+                    .map(Some)
+                    .ok_or(DecodeError::InvalidHex { offset: self.position, found: ch as char }),
+            }
+        }
+    }
+
+    pub fn read_bit(&mut self) -> Result<u64, DecodeError> {
+        if self.nibble_bits == 0 {
+            self.nibble = self.next_nibble()?
+                .ok_or(DecodeError::OutOfBits { offset: self.position })?;
+            self.nibble_bits = 4;
+        }
+
+        self.nibble_bits -= 1;
+        self.position += 1;
+
+        Ok((self.nibble >> self.nibble_bits) & 1)
+    }
+
+    pub fn read(&mut self, count: usize) -> Result<u64, DecodeError> {
+        let mut val: u64 = 0;
+        for _ in 0..count {
+            val = val * 2 + self.read_bit()?;
+        }
+
+        Ok(val)
+    }
+
+    // consumes the rest of the transmission, which may only be zeros
+    pub fn finish(&mut self) -> Result<(), DecodeError> {
+        loop {
+            if self.nibble_bits == 0 {
+                match self.next_nibble()? {
+                    None => return Ok(()),
+                    Some(nibble) => {
+                        self.nibble = nibble;
+                        self.nibble_bits = 4;
+                    },
+                }
+            }
+
+            let offset = self.position;
+            if self.read_bit()? == 1 {
+                return Err(DecodeError::NonZeroPadding { offset });
+            }
+        }
+    }
+}
+
+impl Display for DecodeError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            DecodeError::OutOfBits { offset } =>
+                write!(f, "ran out of bits at bit {}", offset),
+            DecodeError::InvalidHex { offset, found } =>
+                write!(f, "invalid hex character '{}' at bit {}", found, offset),
+            DecodeError::LengthMismatch { offset, expected, actual } =>
+                write!(f, "sub-packets starting at bit {} take {} bits, length field says {}", offset, actual, expected),
+            DecodeError::NonZeroPadding { offset } =>
+                write!(f, "padding after the outermost packet has a set bit at bit {}", offset),
+            DecodeError::LiteralOverflow { offset } =>
+                write!(f, "literal value doesn't fit in 64 bits at bit {}", offset),
+            DecodeError::Read { offset, message } =>
+                write!(f, "couldn't read transmission at bit {}, {}", offset, message),
+        }
+    }
+}
+
+impl Display for EncodeError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            EncodeError::VersionTooLarge { offset, version } =>
+                write!(f, "version {} at bit {} doesn't fit in 3 bits", version, offset),
+            EncodeError::TypeIdTooLarge { offset, type_id } =>
+                write!(f, "type id {} at bit {} doesn't fit in 3 bits", type_id, offset),
+            EncodeError::OperatorAsLiteral { offset } =>
+                write!(f, "operator at bit {} has the literal type id {}", offset, LITERAL_TYPE_ID),
+            EncodeError::TooManyPackets { offset, count } =>
+                write!(f, "operator at bit {} has {} sub-packets, at most {} fit the count field", offset, count, MAX_PACKET_COUNT),
+            EncodeError::TooManyBits { offset, bits } =>
+                write!(f, "operator at bit {} has {} bits of sub-packets, at most {} fit the length field", offset, bits, MAX_PACKET_BITS),
+        }
+    }
+}
+
+impl Display for EvalError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            EvalError::Arity { version, type_id, operands } =>
+                write!(f, "v{} {} (type {}) can't be evaluated with {} operands", version, operator_name(*type_id), type_id, operands),
+            EvalError::UnknownType { version, type_id } =>
+                write!(f, "v{} has unknown operator type {}", version, type_id),
+            EvalError::Overflow { version, type_id } =>
+                write!(f, "v{} {} (type {}) overflows 64 bits", version, operator_name(*type_id), type_id),
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+impl std::error::Error for EncodeError {}
+impl std::error::Error for EvalError {}
+
+impl BitWriter {
+    pub fn write(&mut self, value: u64, count: usize) {
+        for i in (0..count).rev() {
+            self.bits.push((value >> i) & 1 == 1);
+        }
+    }
+
+    // trailing bits are padded with zeros out to a whole hex character
+    pub fn to_hex(&self) -> String {
+        self.bits
+            .chunks(4)
+            .map(|chunk| {
+                let nibble = (0..4).fold(0, |acc, i| acc * 2 + if chunk.get(i) == Some(&true) { 1 } else { 0 });
+                std::char::from_digit(nibble, 16).unwrap().to_ascii_uppercase()
+            })
+            .collect()
+    }
+}
+
+impl Packet {
+    pub fn version(&self) -> u64 {
+        match self {
+            Packet::Literal { version, .. } | Packet::Operator { version, .. } => *version,
+        }
+    }
+
+    pub fn type_id(&self) -> u64 {
+        match self {
+            Packet::Literal { .. } => LITERAL_TYPE_ID,
+            Packet::Operator { type_id, .. } => *type_id,
+        }
+    }
+
+    // number of bits this packet takes up once encoded, including all sub-packets. that's the
+    // smallest encoding, a transmission can spend more (like leading zero literal groups)
+    pub fn bit_len(&self) -> usize {
+        match self {
+            Packet::Literal { value, .. } => 6 + literal_groups(*value) * 5,
+            Packet::Operator { length_type, packets, .. } => {
+                let header = 7 + match length_type {
+                    LengthType::TotalBits => 15,
+                    LengthType::PacketCount => 11,
+                };
+
+                header + packets.iter().map(|p| p.bit_len()).sum::<usize>()
+            },
+        }
+    }
+
+    pub fn encode(&self) -> Result<String, EncodeError> {
+        let mut writer = BitWriter::default();
+        self.write_to(&mut writer)?;
+
+        Ok(writer.to_hex())
+    }
+
+    // fails rather than write a field that's too small for its value, which would decode
+    // as a different tree
+    pub fn write_to(&self, writer: &mut BitWriter) -> Result<(), EncodeError> {
+        let offset = writer.bits.len();
+        if self.version() > MAX_HEADER_FIELD {
+            return Err(EncodeError::VersionTooLarge { offset, version: self.version() });
+        }
+        if self.type_id() > MAX_HEADER_FIELD {
+            return Err(EncodeError::TypeIdTooLarge { offset, type_id: self.type_id() });
+        }
+        if let Packet::Operator { type_id: LITERAL_TYPE_ID, .. } = self {
+            return Err(EncodeError::OperatorAsLiteral { offset });
+        }
+
+        writer.write(self.version(), 3);
+        writer.write(self.type_id(), 3);
+
+        match self {
+            Packet::Literal { value, .. } => {
+                let groups = literal_groups(*value);
+                for group in (0..groups).rev() {
+                    let control = if group > 0 { 1 } else { 0 };
+                    writer.write(control, 1);
+                    writer.write(value >> (group * 4), 4);
+                }
+            },
+            Packet::Operator { length_type, packets, .. } => {
+                match length_type {
+                    LengthType::TotalBits => {
+                        let bits = packets.iter().map(|p| p.bit_len()).sum::<usize>();
+                        if bits > MAX_PACKET_BITS {
+                            return Err(EncodeError::TooManyBits { offset, bits });
+                        }
+
+                        writer.write(0, 1);
+                        writer.write(bits as u64, 15);
+                    },
+                    LengthType::PacketCount => {
+                        if packets.len() > MAX_PACKET_COUNT {
+                            return Err(EncodeError::TooManyPackets { offset, count: packets.len() });
+                        }
+
+                        writer.write(1, 1);
+                        writer.write(packets.len() as u64, 11);
+                    },
+                }
+
+                for packet in packets {
+                    packet.write_to(writer)?;
+                }
+            },
+        }
+
+        Ok(())
+    }
+
+    fn fmt_tree(&self, f: &mut Formatter, depth: usize) -> fmt::Result {
+        let indent = "  ".repeat(depth);
+
+        match self {
+            Packet::Literal { version, value } => {
+                writeln!(f, "{}v{} literal {} (encoded as {} bits)", indent, version, value, self.bit_len())
+            },
+            Packet::Operator { version, type_id, length_type, packets } => {
+                let length = match length_type {
+                    LengthType::TotalBits =>
+                        format!("{} bits of sub-packets", packets.iter().map(|p| p.bit_len()).sum::<usize>()),
+                    LengthType::PacketCount =>
+                        format!("{} sub-packets", packets.len()),
+                };
+
+                writeln!(f, "{}v{} {} (type {}, encoded as {} bits with {})",
+                    indent, version, operator_name(*type_id), type_id, self.bit_len(), length)?;
+
+                for packet in packets {
+                    packet.fmt_tree(f, depth + 1)?;
+                }
+
+                Ok(())
+            },
+        }
+    }
+}
+
+impl Display for Packet {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        self.fmt_tree(f, 0)
+    }
+}
+
+// expression view of the packet tree, operators are the type ids 0-3 and 5-7
+impl Packet {
+    pub fn evaluate(&self) -> Result<u64, EvalError> {
+        match self {
+            Packet::Literal { value, .. } => Ok(*value),
+            Packet::Operator { version, type_id, packets, .. } => {
+                let values = packets.iter()
+                    .map(|p| p.evaluate())
+                    .collect::<Result<Vec<u64>, EvalError>>()?;
+
+                apply(*version, *type_id, &values)
+            },
+        }
+    }
+
+    // levels of nesting, a lone literal has a depth of 1
+    pub fn depth(&self) -> usize {
+        match self {
+            Packet::Literal { .. } => 1,
+            Packet::Operator { packets, .. } =>
+                1 + packets.iter().map(|p| p.depth()).max().unwrap_or(0),
+        }
+    }
+
+    // replaces every well formed sub-tree with the literal it evaluates to, so only
+    // the malformed operators (and the path down to them) are left standing
+    pub fn fold(&self) -> Packet {
+        if let Ok(value) = self.evaluate() {
+            return Packet::Literal { version: self.version(), value };
+        }
+
+        match self {
+            Packet::Literal { .. } => self.clone(),
+            Packet::Operator { version, type_id, length_type, packets } => Packet::Operator {
+                version: *version,
+                type_id: *type_id,
+                length_type: *length_type,
+                packets: packets.iter().map(|p| p.fold()).collect(),
+            },
+        }
+    }
+
+    // describes every operator with the wrong number of operands or an unknown type id
+    pub fn arity_errors(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        self.collect_arity_errors(1, &mut errors);
+
+        errors
+    }
+
+    pub fn to_infix(&self) -> String {
+        match self {
+            Packet::Literal { value, .. } => value.to_string(),
+            Packet::Operator { type_id, packets, .. } => {
+                if self.is_infix() {
+                    let symbol = match type_id {
+                        0 => " + ", 1 => " * ", 5 => " > ", 6 => " < ", _ => " == ",
+                    };
+
+                    packets.iter()
+                        .map(|p| if p.is_infix() && !(p.type_id() == *type_id && *type_id <= 1) {
+                            format!("({})", p.to_infix())
+                        } else {
+                            p.to_infix()
+                        })
+                        .collect::<Vec<String>>()
+                        .join(symbol)
+                } else {
+                    let name = match type_id {
+                        0 => String::from("sum"),
+                        1 => String::from("product"),
+                        2 => String::from("min"),
+                        3 => String::from("max"),
+                        5 => String::from("gt"),
+                        6 => String::from("lt"),
+                        7 => String::from("eq"),
+                        _ => format!("op{}", type_id),
+                    };
+
+                    format!("{}({})", name, packets.iter()
+                        .map(|p| p.to_infix())
+                        .collect::<Vec<String>>()
+                        .join(", "))
+                }
+            },
+        }
+    }
+
+    fn arity_ok(&self) -> bool {
+        match self {
+            Packet::Literal { .. } => true,
+            Packet::Operator { type_id, packets, .. } => arity_ok(*type_id, packets.len()),
+        }
+    }
+
+    // sums and products of several operands and well formed comparisons are written as
+    // infix, everything else falls back to function call style
+    fn is_infix(&self) -> bool {
+        match self {
+            Packet::Literal { .. } => false,
+            Packet::Operator { type_id, packets, .. } => match type_id {
+                0 | 1 => packets.len() >= 2,
+                5..=7 => packets.len() == 2,
+                _ => false,
+            },
+        }
+    }
+
+    fn collect_arity_errors(&self, depth: usize, errors: &mut Vec<String>) {
+        if let Packet::Operator { version, type_id, packets, .. } = self {
+            if !self.arity_ok() {
+                let expected = match type_id {
+                    0..=3 => "at least 1",
+                    5..=7 => "exactly 2",
+                    _ => "a known type id",
+                };
+
+                errors.push(format!("v{} {} (type {}) at depth {} has {} operands, expected {}",
+                    version, operator_name(*type_id), type_id, depth, packets.len(), expected));
+            }
+
+            for packet in packets {
+                packet.collect_arity_errors(depth + 1, errors);
+            }
+        }
+    }
+}
+
+// strict decode, the transmission must be exactly one packet followed by zero padding
+pub fn decode(hex: &str) -> Result<Packet, DecodeError> {
+    visit(BitReader::from_hex(hex), &mut TreeBuilder)
+}
+
+// keeps every packet read before the first error, operators that were cut off hold the
+// sub-packets they managed to finish
+pub fn decode_lenient(hex: &str) -> PartialDecode {
+    let mut builder = TreeBuilder;
+    let mut decoder = Decoder::new(BitReader::from_hex(hex), &mut builder, true);
+    let packet = decoder.packet();
+
+    let error = match packet {
+        Err(ref error) => Some(error.clone()),
+        Ok(_) => decoder.cut_off.clone().or_else(|| decoder.bits.finish().err()),
+    };
+
+    PartialDecode {
+        packet: packet.ok(),
+        errors: error.into_iter().collect(),
+    }
+}
+
+// strictly decodes a single transmission, handing each packet to the visitor as it's read
+pub fn visit<R: Read, V: Visitor>(bits: BitReader<R>, visitor: &mut V) -> Result<V::Output, DecodeError> {
+    let mut decoder = Decoder::new(bits, visitor, false);
+    let output = decoder.packet()?;
+    decoder.bits.finish()?;
+
+    Ok(output)
+}
+
+impl Visitor for TreeBuilder {
+    type Output = Packet;
+
+    fn literal(&mut self, version: u64, value: u64) -> Packet {
+        Packet::Literal { version, value }
+    }
+
+    fn operator(&mut self, version: u64, type_id: u64, length_type: LengthType, packets: Vec<Packet>) -> Packet {
+        Packet::Operator { version, type_id, length_type, packets }
+    }
+}
+
+impl Visitor for VersionSum {
+    type Output = u64;
+
+    fn literal(&mut self, version: u64, _: u64) -> u64 {
+        version
+    }
+
+    fn operator(&mut self, version: u64, _: u64, _: LengthType, operands: Vec<u64>) -> u64 {
+        version + operands.iter().sum::<u64>()
+    }
+}
+
+impl Visitor for Evaluator {
+    type Output = Result<u64, EvalError>;
+
+    fn literal(&mut self, _: u64, value: u64) -> Self::Output {
+        Ok(value)
+    }
+
+    fn operator(&mut self, version: u64, type_id: u64, _: LengthType, operands: Vec<Self::Output>) -> Self::Output {
+        let values = operands.into_iter().collect::<Result<Vec<u64>, EvalError>>()?;
+        apply(version, type_id, &values)
+    }
+}
+
+// runs two visitors side by side, so one pass over a transmission that can't be read twice
+// (like stdin) gives both answers
+impl<A: Visitor, B: Visitor> Visitor for (A, B) {
+    type Output = (A::Output, B::Output);
+
+    fn literal(&mut self, version: u64, value: u64) -> Self::Output {
+        (self.0.literal(version, value), self.1.literal(version, value))
+    }
+
+    fn operator(&mut self, version: u64, type_id: u64, length_type: LengthType, operands: Vec<Self::Output>) -> Self::Output {
+        let (first, second): (Vec<A::Output>, Vec<B::Output>) = operands.into_iter().unzip();
+        (self.0.operator(version, type_id, length_type, first), self.1.operator(version, type_id, length_type, second))
+    }
+}
+
+struct Decoder<'v, R: Read, V: Visitor> {
+    bits: BitReader<R>,
+    visitor: &'v mut V,
+    lenient: bool,
+    cut_off: Option<DecodeError>, /* error that stopped a lenient decode */
+}
+
+// an operator whose sub-packets are still being read. these are kept on a stack rather
+// than in recursive calls, so deeply nested transmissions can't overflow the call stack
+struct Frame<O> {
+    version: u64,
+    type_id: u64,
+    length_type: LengthType,
+    remaining: Remaining,
+    operands: Vec<O>,
+}
+
+enum Remaining {
+    Bits { start: usize, length: usize },
+    Packets(u64),
+}
+
+impl<'v, R: Read, V: Visitor> Decoder<'v, R, V> {
+    fn new(bits: BitReader<R>, visitor: &'v mut V, lenient: bool) -> Decoder<'v, R, V> {
+        Decoder {
+            bits,
+            visitor,
+            lenient,
+            cut_off: None,
+        }
+    }
+
+    fn packet(&mut self) -> Result<V::Output, DecodeError> {
+        let mut open: Vec<Frame<V::Output>> = Vec::new();
+
+        loop {
+            // an error in a sub-packet cuts off the innermost open operator, which keeps
+            // the operands it managed to finish
+            let mut finished = match self.start_packet(&mut open) {
+                Ok(finished) => finished,
+                Err(error) if open.is_empty() => return Err(error),
+                Err(error) => {
+                    self.cut(error)?;
+                    None
+                },
+            };
+
+            while let Some(frame) = open.last_mut() {
+                if let Some(output) = finished.take() {
+                    frame.operands.push(output);
+                    if let Remaining::Packets(count) = &mut frame.remaining {
+                        *count -= 1;
+                    }
+                }
+
+                let complete = self.cut_off.is_some() || match frame.remaining {
+                    Remaining::Bits { start, length } => self.bits.position() - start >= length,
+                    Remaining::Packets(count) => count == 0,
+                };
+                if !complete {
+                    break;
+                }
+
+                let frame = open.pop().unwrap();
+                if let Remaining::Bits { start, length } = frame.remaining {
+                    let actual = self.bits.position() - start;
+                    if self.cut_off.is_none() && actual != length {
+                        self.cut(DecodeError::LengthMismatch { offset: start, expected: length, actual })?;
+                    }
+                }
+
+                finished = Some(self.visitor.operator(frame.version, frame.type_id, frame.length_type, frame.operands));
+            }
+
+            if let (true, Some(output)) = (open.is_empty(), finished) {
+                return Ok(output);
+            }
+        }
+    }
+
+    // reads a packet header, a literal is finished right away while an operator is pushed
+    // onto the open stack until its sub-packets have been read
+    fn start_packet(&mut self, open: &mut Vec<Frame<V::Output>>) -> Result<Option<V::Output>, DecodeError> {
+        let version = self.bits.read(3)?;
+        let type_id = self.bits.read(3)?;
+
+        if type_id == LITERAL_TYPE_ID {
+            let value = parse_literal_value(&mut self.bits)?;
+            return Ok(Some(self.visitor.literal(version, value)));
+        }
+
+        let length_type = match self.bits.read(1)? {
+            0 => LengthType::TotalBits,
+            _ => LengthType::PacketCount,
+        };
+
+        // the operator is open from here on, so a length that can't be read cuts it off
+        // rather than its parent
+        open.push(Frame { version, type_id, length_type, remaining: Remaining::Packets(0), operands: Vec::new() });
+        let remaining = match length_type {
+            LengthType::TotalBits => {
+                let length = self.bits.read(15)? as usize;
+                Remaining::Bits { start: self.bits.position(), length }
+            },
+            LengthType::PacketCount => Remaining::Packets(self.bits.read(11)?),
+        };
+        open.last_mut().unwrap().remaining = remaining;
+
+        Ok(None)
+    }
+
+    // a lenient decode remembers the first error and stops reading, a strict one fails
+    fn cut(&mut self, error: DecodeError) -> Result<(), DecodeError> {
+        if !self.lenient {
+            return Err(error);
+        }
+        self.cut_off.get_or_insert(error);
+
+        Ok(())
+    }
+}
+
+fn arity_ok(type_id: u64, operands: usize) -> bool {
+    match type_id {
+        0..=3 => operands > 0,
+        5..=7 => operands == 2,
+        _ => false,
+    }
+}
+
+fn apply(version: u64, type_id: u64, values: &[u64]) -> Result<u64, EvalError> {
+    if type_id > 7 || type_id == LITERAL_TYPE_ID {
+        return Err(EvalError::UnknownType { version, type_id });
+    }
+    if !arity_ok(type_id, values.len()) {
+        return Err(EvalError::Arity { version, type_id, operands: values.len() });
+    }
+
+    let overflow = EvalError::Overflow { version, type_id };
+    Ok(match type_id {
+        0 => values.iter().try_fold(0u64, |sum, &value| sum.checked_add(value)).ok_or(overflow)?,
+        1 => values.iter().try_fold(1u64, |product, &value| product.checked_mul(value)).ok_or(overflow)?,
+        2 => values.iter().copied().min().unwrap(),
+        3 => values.iter().copied().max().unwrap(),
+        5 => if values[0] > values[1] { 1 } else { 0 },
+        6 => if values[0] < values[1] { 1 } else { 0 },
+        _ => if values[0] == values[1] { 1 } else { 0 },
+    })
+}
+
+pub fn operator_name(type_id: u64) -> &'static str {
+    match type_id {
+        0 => "sum",
+        1 => "product",
+        2 => "minimum",
+        3 => "maximum",
+        4 => "literal",
+        5 => "greater than",
+        6 => "less than",
+        7 => "equal to",
+        _ => "unknown",
+    }
+}
+
+fn parse_literal_value<R: Read>(bits: &mut BitReader<R>) -> Result<u64, DecodeError> {
+    let mut val: u64 = 0;
+    loop {
+        let offset = bits.position();
+        let control = bits.read(1)?;
+        let group = bits.read(4)?;
+        val = val.checked_mul(16).ok_or(DecodeError::LiteralOverflow { offset })? + group;
+
+        if control == 0 { break }
+    }
+
+    Ok(val)
+}
+
+// literal values are written in groups of 4 bits, always at least one group
+fn literal_groups(value: u64) -> usize {
+    let significant_bits = 64 - value.leading_zeros() as usize;
+    usize::max(1, significant_bits.div_ceil(4))
+}
+
+fn hex_value(ch: u8) -> Option<u64> {
+    match ch {
+        b'0'..=b'9' => Some((ch - b'0') as u64),
+        b'A'..=b'F' => Some((ch - b'A' + 10) as u64),
+        b'a'..=b'f' => Some((ch - b'a' + 10) as u64),
+        _ => None,
+    }
+}
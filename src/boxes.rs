@@ -0,0 +1,511 @@
+use std::cmp;
+use std::collections::HashMap;
+
+// an axis aligned box in N dimensions. bounds are half-open, each axis runs from start up
+// to but not including end, so widths are plain differences and cut boxes share an edge.
+// build one with `inclusive` for puzzle ranges like x=10..12, or `half_open`
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct Cuboid<const N: usize> {
+    start: [i64; N],
+    end: [i64; N],
+}
+
+// a union of boxes, kept as pieces that never overlap so the volume is a plain sum
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct CuboidSet<const N: usize> {
+    pieces: Vec<Cuboid<N>>,
+}
+
+impl<const N: usize> Cuboid<N> {
+    // both min and max are inside the box
+    pub fn inclusive(min: [i64; N], max: [i64; N]) -> Cuboid<N> {
+        Cuboid { start: min, end: max.map(|bound| bound + 1) }
+    }
+
+    // start is inside the box and end is just past it
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn half_open(start: [i64; N], end: [i64; N]) -> Cuboid<N> {
+        Cuboid { start, end }
+    }
+
+    // a box that ends at or before its start on any axis holds nothing
+    pub fn is_empty(&self) -> bool {
+        (0..N).any(|axis| self.end[axis] <= self.start[axis])
+    }
+
+    pub fn volume(&self) -> i64 {
+        if self.is_empty() {
+            return 0;
+        }
+
+        (0..N).map(|axis| self.end[axis] - self.start[axis]).product()
+    }
+
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn contains_point(&self, point: &[i64; N]) -> bool {
+        (0..N).all(|axis| self.start[axis] <= point[axis] && point[axis] < self.end[axis])
+    }
+
+    pub fn contains(&self, other: &Cuboid<N>) -> bool {
+        other.is_empty() || (0..N).all(|axis| {
+            self.start[axis] <= other.start[axis] && other.end[axis] <= self.end[axis]
+        })
+    }
+
+    pub fn intersection(&self, other: &Cuboid<N>) -> Option<Cuboid<N>> {
+        let mut overlap = *self;
+
+        for axis in 0..N {
+            overlap.start[axis] = cmp::max(self.start[axis], other.start[axis]);
+            overlap.end[axis] = cmp::min(self.end[axis], other.end[axis]);
+        }
+
+        if overlap.is_empty() { None } else { Some(overlap) }
+    }
+
+    // what's left of this box once `other` is cut out, as at most 2N slabs. each axis in
+    // turn trims off the parts below and above `other` and narrows down to the overlap
+    pub fn difference(&self, other: &Cuboid<N>) -> Vec<Cuboid<N>> {
+        let overlap = match self.intersection(other) {
+            Some(overlap) => overlap,
+            None => return if self.is_empty() { vec![] } else { vec![*self] },
+        };
+
+        let mut slabs = Vec::new();
+        let mut rest = *self;
+
+        for axis in 0..N {
+            if rest.start[axis] < overlap.start[axis] {
+                let mut below = rest;
+                below.end[axis] = overlap.start[axis];
+                slabs.push(below);
+            }
+
+            if overlap.end[axis] < rest.end[axis] {
+                let mut above = rest;
+                above.start[axis] = overlap.end[axis];
+                slabs.push(above);
+            }
+
+            rest.start[axis] = overlap.start[axis];
+            rest.end[axis] = overlap.end[axis];
+        }
+
+        slabs
+    }
+}
+
+impl<const N: usize> CuboidSet<N> {
+    pub fn new() -> CuboidSet<N> {
+        CuboidSet { pieces: Vec::new() }
+    }
+
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn pieces(&self) -> &[Cuboid<N>] {
+        &self.pieces
+    }
+
+    pub fn volume(&self) -> i64 {
+        self.pieces.iter().map(|piece| piece.volume()).sum()
+    }
+
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn contains_point(&self, point: &[i64; N]) -> bool {
+        self.pieces.iter().any(|piece| piece.contains_point(point))
+    }
+
+    // true if every part of `cuboid` is covered by the set
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn contains(&self, cuboid: &Cuboid<N>) -> bool {
+        let mut uncovered = CuboidSet::new();
+        uncovered.insert(*cuboid);
+        uncovered.difference(self).pieces.is_empty()
+    }
+
+    pub fn insert(&mut self, cuboid: Cuboid<N>) {
+        if cuboid.is_empty() {
+            return;
+        }
+
+        self.remove(&cuboid);
+        self.pieces.push(cuboid);
+    }
+
+    pub fn remove(&mut self, cuboid: &Cuboid<N>) {
+        let mut kept = Vec::with_capacity(self.pieces.len());
+
+        for piece in self.pieces.iter() {
+            if piece.intersection(cuboid).is_none() {
+                kept.push(*piece);
+            } else {
+                kept.append(&mut piece.difference(cuboid));
+            }
+        }
+
+        self.pieces = kept;
+    }
+
+    // only the parts of the set inside `region`
+    pub fn clip(&self, region: &Cuboid<N>) -> CuboidSet<N> {
+        let pieces = self.pieces.iter()
+            .filter_map(|piece| {
+                if region.contains(piece) { Some(*piece) } else { piece.intersection(region) }
+            })
+            .collect();
+
+        CuboidSet { pieces }
+    }
+
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn union(&self, other: &CuboidSet<N>) -> CuboidSet<N> {
+        let mut result = self.clone();
+        for piece in other.pieces.iter() {
+            result.insert(*piece);
+        }
+
+        result
+    }
+
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn intersection(&self, other: &CuboidSet<N>) -> CuboidSet<N> {
+        let mut result = CuboidSet::new();
+
+        // pieces within each set are disjoint, so their overlaps are too
+        for piece in self.pieces.iter() {
+            for other_piece in other.pieces.iter() {
+                if let Some(overlap) = piece.intersection(other_piece) {
+                    result.pieces.push(overlap);
+                }
+            }
+        }
+
+        result
+    }
+
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn difference(&self, other: &CuboidSet<N>) -> CuboidSet<N> {
+        let mut result = self.clone();
+        for piece in other.pieces.iter() {
+            result.remove(piece);
+        }
+
+        result
+    }
+
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn symmetric_difference(&self, other: &CuboidSet<N>) -> CuboidSet<N> {
+        let mut result = self.difference(other);
+        result.pieces.append(&mut other.difference(self).pieces);
+
+        result
+    }
+}
+
+// volume left on after switching each box on (true) or off (false) in order. every box
+// that's switched on counts once, and every overlap with an earlier box is added back or
+// taken away again so nothing is counted twice. equal boxes share one signed count
+pub fn signed_volume<const N: usize>(steps: &[(Cuboid<N>, bool)]) -> i64 {
+    let mut counts: HashMap<Cuboid<N>, i64> = HashMap::new();
+
+    for &(cuboid, on) in steps {
+        let mut changes: HashMap<Cuboid<N>, i64> = HashMap::new();
+
+        for (existing, &count) in counts.iter() {
+            if let Some(overlap) = cuboid.intersection(existing) {
+                *changes.entry(overlap).or_insert(0) -= count;
+            }
+        }
+
+        if on && !cuboid.is_empty() {
+            *changes.entry(cuboid).or_insert(0) += 1;
+        }
+
+        for (changed, change) in changes {
+            *counts.entry(changed).or_insert(0) += change;
+        }
+        counts.retain(|_, count| *count != 0);
+    }
+
+    counts.iter().map(|(cuboid, count)| cuboid.volume() * count).sum()
+}
+
+// the same volume, found by cutting space along every box edge into cells that are each
+// wholly on or off and tracking them in a bitmap. the last axis runs along the bitmap rows
+pub fn compressed_volume<const N: usize>(steps: &[(Cuboid<N>, bool)]) -> i64 {
+    let steps: Vec<&(Cuboid<N>, bool)> = steps.iter().filter(|(cuboid, _)| !cuboid.is_empty()).collect();
+    if steps.is_empty() || N == 0 {
+        return 0;
+    }
+
+    // cell i on an axis runs from edges[i] up to, but not including, edges[i + 1]
+    let edges: Vec<Vec<i64>> = (0..N)
+        .map(|axis| {
+            let mut edges: Vec<i64> = steps.iter()
+                .flat_map(|(cuboid, _)| [cuboid.start[axis], cuboid.end[axis]])
+                .collect();
+            edges.sort();
+            edges.dedup();
+            edges
+        })
+        .collect();
+    let cells: Vec<usize> = edges.iter().map(|edges| edges.len() - 1).collect();
+    let row_words = cells[N - 1].div_ceil(64);
+    let rows: usize = cells[..N - 1].iter().product();
+    let mut bitmap = vec![0u64; rows * row_words];
+
+    for (cuboid, on) in steps {
+        let span: Vec<(usize, usize)> = (0..N)
+            .map(|axis| {
+                let start = edges[axis].binary_search(&cuboid.start[axis]).unwrap();
+                let stop = edges[axis].binary_search(&cuboid.end[axis]).unwrap();
+                (start, stop)
+            })
+            .collect();
+
+        for row in rows_within(&span[..N - 1], &cells[..N - 1]) {
+            let words = &mut bitmap[row * row_words..(row + 1) * row_words];
+            set_bits(words, span[N - 1].0, span[N - 1].1, *on);
+        }
+    }
+
+    let widths: Vec<Vec<i64>> = edges.iter()
+        .map(|edges| edges.windows(2).map(|pair| pair[1] - pair[0]).collect())
+        .collect();
+    let mut volume = 0;
+    let mut cell = vec![0; N - 1];
+
+    for row in 0..rows {
+        let words = &bitmap[row * row_words..(row + 1) * row_words];
+        let row_volume: i64 = (0..cells[N - 1])
+            .filter(|&bit| words[bit / 64] & (1 << (bit % 64)) != 0)
+            .map(|bit| widths[N - 1][bit])
+            .sum();
+
+        if row_volume > 0 {
+            volume += row_volume * (0..N - 1).map(|axis| widths[axis][cell[axis]]).product::<i64>();
+        }
+
+        // rows are numbered with the last of the leading axes changing fastest
+        for axis in (0..N - 1).rev() {
+            cell[axis] += 1;
+            if cell[axis] < cells[axis] { break; }
+            cell[axis] = 0;
+        }
+    }
+
+    volume
+}
+
+// bitmap row numbers for every cell in the half-open `span` on each leading axis
+fn rows_within(span: &[(usize, usize)], cells: &[usize]) -> Vec<usize> {
+    let mut rows = vec![0];
+
+    for (&(start, stop), &size) in span.iter().zip(cells.iter()) {
+        rows = rows.iter()
+            .flat_map(|row| (start..stop).map(move |cell| row * size + cell))
+            .collect();
+    }
+
+    rows
+}
+
+// switches bits start..stop a word at a time
+fn set_bits(words: &mut [u64], start: usize, stop: usize, on: bool) {
+    let mut bit = start;
+
+    while bit < stop {
+        let offset = bit % 64;
+        let count = cmp::min(64 - offset, stop - bit);
+        let mask = if count == 64 { !0 } else { ((1u64 << count) - 1) << offset };
+
+        if on { words[bit / 64] |= mask } else { words[bit / 64] &= !mask }
+        bit += count;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const REACH: i64 = 6;
+    const MAX_STEPS: usize = 8;
+    const TRIALS: usize = 500;
+
+    type Steps = Vec<(Cuboid<3>, bool)>;
+
+    // whether a cube belongs in a set operation's result, given whether each side has it
+    type Membership = fn(bool, bool) -> bool;
+
+    // small deterministic generator, nothing here needs real randomness
+    struct Random(u64);
+
+    impl Random {
+        fn next(&mut self) -> u64 {
+            self.0 = self.0.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            self.0 >> 33
+        }
+
+        // both ends of every range land in the checked space. a quarter are read as half-open,
+        // which also turns up empty boxes where the ends meet
+        fn cuboid(&mut self) -> Cuboid<3> {
+            let span = 2 * REACH as u64 + 1;
+            let mut min = [0; 3];
+            let mut max = [0; 3];
+
+            for axis in 0..3 {
+                let a = (self.next() % span) as i64 - REACH;
+                let b = (self.next() % span) as i64 - REACH;
+                (min[axis], max[axis]) = (a.min(b), a.max(b));
+            }
+
+            if self.next().is_multiple_of(4) { Cuboid::half_open(min, max) } else { Cuboid::inclusive(min, max) }
+        }
+
+        fn steps(&mut self) -> Steps {
+            let count = 1 + (self.next() as usize) % MAX_STEPS;
+            (0..count).map(|_| (self.cuboid(), !self.next().is_multiple_of(3))).collect()
+        }
+    }
+
+    fn cubes() -> Vec<[i64; 3]> {
+        let mut cubes = Vec::new();
+        for x in -REACH..=REACH {
+            for y in -REACH..=REACH {
+                for z in -REACH..=REACH {
+                    cubes.push([x, y, z]);
+                }
+            }
+        }
+
+        cubes
+    }
+
+    // the last step that covers a cube decides whether it's on
+    fn is_on(steps: &[(Cuboid<3>, bool)], cube: &[i64; 3]) -> bool {
+        steps.iter().rev()
+            .find(|(cuboid, _)| cuboid.contains_point(cube))
+            .is_some_and(|&(_, on)| on)
+    }
+
+    fn build_set(steps: &[(Cuboid<3>, bool)]) -> CuboidSet<3> {
+        let mut set = CuboidSet::new();
+        for &(cuboid, on) in steps {
+            if on { set.insert(cuboid) } else { set.remove(&cuboid) }
+        }
+
+        set
+    }
+
+    // every trial gets its own instruction list and region from one seeded generator
+    fn trials() -> Vec<(Steps, Cuboid<3>)> {
+        let mut random = Random(0x2021_0022);
+        (0..TRIALS).map(|_| (random.steps(), random.cuboid())).collect()
+    }
+
+    #[test]
+    fn inclusive_ranges_count_both_ends() {
+        assert_eq!(Cuboid::inclusive([10, 10, 10], [12, 12, 12]).volume(), 27);
+        assert_eq!(Cuboid::inclusive([5, 5, 5], [5, 5, 5]).volume(), 1);
+        assert_eq!(Cuboid::inclusive([-1, 0, 0], [1, 0, 0]).volume(), 3);
+        assert!(Cuboid::inclusive([10, 10, 10], [12, 12, 12]).contains_point(&[12, 12, 12]));
+        assert!(!Cuboid::inclusive([10, 10, 10], [12, 12, 12]).contains_point(&[13, 12, 12]));
+    }
+
+    #[test]
+    fn half_open_ranges_leave_out_the_end() {
+        assert_eq!(Cuboid::half_open([10, 10, 10], [12, 12, 12]).volume(), 8);
+        assert!(Cuboid::half_open([0, 0, 0], [0, 4, 4]).is_empty());
+        assert_eq!(Cuboid::half_open([0, 0, 0], [0, 4, 4]).volume(), 0);
+        assert!(!Cuboid::half_open([0, 0, 0], [2, 2, 2]).contains_point(&[2, 0, 0]));
+    }
+
+    #[test]
+    fn touching_boxes_dont_overlap() {
+        let left = Cuboid::inclusive([0, 0, 0], [1, 1, 1]);
+        let right = Cuboid::inclusive([2, 0, 0], [3, 1, 1]);
+
+        assert_eq!(left.intersection(&right), None);
+        assert_eq!(left.difference(&right), vec![left]);
+        assert_eq!(left.intersection(&Cuboid::inclusive([1, 1, 1], [3, 3, 3])).map(|c| c.volume()), Some(1));
+    }
+
+    #[test]
+    fn difference_leaves_disjoint_slabs() {
+        let outer = Cuboid::inclusive([0, 0, 0], [4, 4, 4]);
+        let inner = Cuboid::inclusive([1, 1, 1], [2, 2, 2]);
+        let slabs = outer.difference(&inner);
+
+        assert_eq!(slabs.len(), 6);
+        assert_eq!(slabs.iter().map(|slab| slab.volume()).sum::<i64>(), 125 - 8);
+        assert!(slabs.iter().all(|slab| slab.intersection(&inner).is_none()));
+    }
+
+    #[test]
+    fn volume_engines_match_brute_force() {
+        let cubes = cubes();
+
+        for (trial, (steps, region)) in trials().iter().enumerate() {
+            let on = cubes.iter().filter(|cube| is_on(steps, cube)).count() as i64;
+            let clipped: Steps = steps.iter()
+                .filter_map(|&(cuboid, on)| cuboid.intersection(region).map(|inside| (inside, on)))
+                .collect();
+            let on_inside = cubes.iter().filter(|cube| is_on(steps, cube) && region.contains_point(cube)).count() as i64;
+            let set = build_set(steps);
+
+            assert_eq!(set.volume(), on, "split volume, trial {}", trial);
+            assert_eq!(set.clip(region).volume(), on_inside, "split clip, trial {}", trial);
+            assert_eq!(signed_volume(steps), on, "signed volume, trial {}", trial);
+            assert_eq!(signed_volume(&clipped), on_inside, "signed clip, trial {}", trial);
+            assert_eq!(compressed_volume(steps), on, "compressed volume, trial {}", trial);
+            assert_eq!(compressed_volume(&clipped), on_inside, "compressed clip, trial {}", trial);
+        }
+    }
+
+    // splits each instruction list in two and checks every set operation on the halves
+    #[test]
+    fn set_operations_match_brute_force() {
+        let cubes = cubes();
+
+        for (trial, (steps, _)) in trials().iter().enumerate() {
+            let (first, second) = steps.split_at(steps.len() / 2);
+            let (left, right) = (build_set(first), build_set(second));
+            let operations: [(&str, CuboidSet<3>, Membership); 4] = [
+                ("union", left.union(&right), |l, r| l || r),
+                ("intersection", left.intersection(&right), |l, r| l && r),
+                ("difference", left.difference(&right), |l, r| l && !r),
+                ("symmetric difference", left.symmetric_difference(&right), |l, r| l != r),
+            ];
+
+            for (name, result, expected) in operations.iter() {
+                for cube in cubes.iter() {
+                    let wanted = expected(left.contains_point(cube), right.contains_point(cube));
+                    assert_eq!(result.contains_point(cube), wanted, "{} at {:?}, trial {}", name, cube, trial);
+                }
+
+                let count = cubes.iter()
+                    .filter(|cube| expected(left.contains_point(cube), right.contains_point(cube)))
+                    .count() as i64;
+                assert_eq!(result.volume(), count, "{} volume, trial {}", name, trial);
+            }
+        }
+    }
+
+    #[test]
+    fn containment_matches_brute_force() {
+        let cubes = cubes();
+
+        for (trial, (steps, region)) in trials().iter().enumerate() {
+            let set = build_set(steps);
+            let covered = cubes.iter()
+                .filter(|cube| region.contains_point(cube))
+                .all(|cube| set.contains_point(cube));
+
+            assert_eq!(set.contains(region), covered, "set containment, trial {}", trial);
+            for piece in set.pieces() {
+                assert!(set.contains(piece), "set should contain its own piece, trial {}", trial);
+                let inside = cubes.iter().filter(|cube| piece.contains_point(cube)).all(|cube| region.contains_point(cube));
+                assert_eq!(region.contains(piece), inside, "box containment, trial {}", trial);
+            }
+        }
+    }
+}
@@ -0,0 +1,251 @@
+use std::ops::{Add, Neg, Sub};
+
+#[derive(Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Debug)]
+pub struct Point {
+    pub x: i32,
+    pub y: i32,
+    pub z: i32,
+}
+
+// one of the 24 ways to turn a scanner, as a matrix of 0 and ±1 with determinant 1
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct Rotation([[i32; 3]; 3]);
+
+// rotate first, then translate
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct Transform {
+    pub rotation: Rotation,
+    pub translation: Point,
+}
+
+impl Point {
+    pub const ORIGIN: Point = Point { x: 0, y: 0, z: 0 };
+
+    pub fn new(x: i32, y: i32, z: i32) -> Point {
+        Point { x, y, z }
+    }
+
+    pub fn manhattan(&self, other: &Point) -> i32 {
+        let diff = *self - *other;
+        diff.x.abs() + diff.y.abs() + diff.z.abs()
+    }
+
+    fn to_array(self) -> [i32; 3] {
+        [self.x, self.y, self.z]
+    }
+
+    fn from_array(coords: [i32; 3]) -> Point {
+        Point { x: coords[0], y: coords[1], z: coords[2] }
+    }
+}
+
+impl Neg for Point {
+    type Output = Point;
+
+    fn neg(self) -> Self::Output {
+        Point {
+            x: -self.x,
+            y: -self.y,
+            z: -self.z,
+        }
+    }
+}
+
+impl Add for Point {
+    type Output = Point;
+
+    fn add(self, other: Self) -> Self::Output {
+        Point {
+            x: self.x + other.x,
+            y: self.y + other.y,
+            z: self.z + other.z,
+        }
+    }
+}
+
+impl Sub for Point {
+    type Output = Point;
+
+    fn sub(self, other: Self) -> Self::Output {
+        Point {
+            x: self.x - other.x,
+            y: self.y - other.y,
+            z: self.z - other.z,
+        }
+    }
+}
+
+impl Rotation {
+    pub const IDENTITY: Rotation = Rotation([[1, 0, 0], [0, 1, 0], [0, 0, 1]]);
+
+    // every signed permutation of the axes that isn't a mirror image
+    pub fn all() -> Vec<Rotation> {
+        let permutations = [[0, 1, 2], [0, 2, 1], [1, 0, 2], [1, 2, 0], [2, 0, 1], [2, 1, 0]];
+        let mut rotations = Vec::with_capacity(24);
+
+        for axes in permutations {
+            for signs in 0..8 {
+                let mut matrix = [[0; 3]; 3];
+                for row in 0..3 {
+                    matrix[row][axes[row]] = if signs & (1 << row) == 0 { 1 } else { -1 };
+                }
+
+                let rotation = Rotation(matrix);
+                if rotation.determinant() == 1 {
+                    rotations.push(rotation);
+                }
+            }
+        }
+
+        rotations
+    }
+
+    // the rotations that turn `from` into `to`, several if the vector has repeated
+    // or zero components
+    pub fn rotate_to(from: Point, to: Point) -> Vec<Rotation> {
+        Self::all().into_iter()
+            .filter(|rotation| rotation.apply(from) == to)
+            .collect()
+    }
+
+    pub fn matrix(&self) -> [[i32; 3]; 3] {
+        self.0
+    }
+
+    pub fn apply(&self, point: Point) -> Point {
+        let coords = point.to_array();
+        let mut rotated = [0; 3];
+
+        for (row, value) in rotated.iter_mut().enumerate() {
+            *value = (0..3).map(|col| self.0[row][col] * coords[col]).sum();
+        }
+
+        Point::from_array(rotated)
+    }
+
+    // `self` applied after `first`
+    pub fn compose(&self, first: &Rotation) -> Rotation {
+        let mut matrix = [[0; 3]; 3];
+
+        for (row, cells) in matrix.iter_mut().enumerate() {
+            for (col, cell) in cells.iter_mut().enumerate() {
+                *cell = (0..3).map(|k| self.0[row][k] * first.0[k][col]).sum();
+            }
+        }
+
+        Rotation(matrix)
+    }
+
+    // rotation matrices are orthogonal, so the transpose undoes them
+    pub fn inverse(&self) -> Rotation {
+        let mut matrix = [[0; 3]; 3];
+
+        for (row, cells) in matrix.iter_mut().enumerate() {
+            for (col, cell) in cells.iter_mut().enumerate() {
+                *cell = self.0[col][row];
+            }
+        }
+
+        Rotation(matrix)
+    }
+
+    pub fn determinant(&self) -> i32 {
+        let m = self.0;
+
+        m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1])
+            - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+            + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0])
+    }
+}
+
+impl Transform {
+    pub const IDENTITY: Transform = Transform {
+        rotation: Rotation::IDENTITY,
+        translation: Point::ORIGIN,
+    };
+
+    pub fn new(rotation: Rotation, translation: Point) -> Transform {
+        Transform { rotation, translation }
+    }
+
+    pub fn apply(&self, point: Point) -> Point {
+        self.rotation.apply(point) + self.translation
+    }
+
+    pub fn apply_all(&self, points: &[Point]) -> Vec<Point> {
+        points.iter().map(|&p| self.apply(p)).collect()
+    }
+
+    // `self` applied after `first`
+    pub fn compose(&self, first: &Transform) -> Transform {
+        Transform {
+            rotation: self.rotation.compose(&first.rotation),
+            translation: self.apply(first.translation),
+        }
+    }
+
+    pub fn inverse(&self) -> Transform {
+        let rotation = self.rotation.inverse();
+
+        Transform {
+            rotation,
+            translation: -rotation.apply(self.translation),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    fn points() -> Vec<Point> {
+        vec![Point::ORIGIN, Point::new(1, 2, 3), Point::new(-5, 0, 7), Point::new(404, -588, -901)]
+    }
+
+    fn transforms() -> Vec<Transform> {
+        Rotation::all().into_iter()
+            .zip(points().into_iter().cycle())
+            .map(|(rotation, translation)| Transform::new(rotation, translation))
+            .collect()
+    }
+
+    #[test]
+    fn there_are_24_distinct_proper_rotations() {
+        let rotations = Rotation::all();
+        let matrices: HashSet<[[i32; 3]; 3]> = rotations.iter().map(|r| r.matrix()).collect();
+
+        assert_eq!(rotations.len(), 24);
+        assert_eq!(matrices.len(), 24);
+        assert!(rotations.iter().all(|r| r.determinant() == 1));
+    }
+
+    #[test]
+    fn rotation_composed_with_its_inverse_is_identity() {
+        for rotation in Rotation::all() {
+            assert_eq!(rotation.compose(&rotation.inverse()), Rotation::IDENTITY);
+            assert_eq!(rotation.inverse().compose(&rotation), Rotation::IDENTITY);
+        }
+    }
+
+    #[test]
+    fn transform_composed_with_its_inverse_is_identity() {
+        for transform in transforms() {
+            assert_eq!(transform.compose(&transform.inverse()), Transform::IDENTITY);
+            assert_eq!(transform.inverse().compose(&transform), Transform::IDENTITY);
+        }
+    }
+
+    #[test]
+    fn composed_transform_applies_the_second_after_the_first() {
+        let transforms = transforms();
+
+        for a in transforms.iter() {
+            for b in transforms.iter() {
+                for point in points() {
+                    assert_eq!(a.compose(b).apply(point), a.apply(b.apply(point)));
+                }
+            }
+        }
+    }
+}
@@ -15,6 +15,10 @@ pub fn read_all(day: &str) -> Result<String> {
     fs::read_to_string(filename(day))
 }
 
+pub fn open(day: &str) -> Result<File> {
+    File::open(filename(day))
+}
+
 fn filename(day: &str) -> String {
     format!("input/{}.txt", day)
 }
\ No newline at end of file
@@ -1,5 +1,11 @@
+mod ballistics;
+mod bits;
+mod boxes;
+mod geometry;
 mod input;
 mod intcode;
+mod options;
+mod pathfinding;
 mod solutions;
 
 use solutions::{year_2015, year_2019, year_2021};
@@ -11,6 +17,7 @@ fn main() {
 
     let mut problems: Vec<String> = env::args().collect();
     problems.remove(0);
+    problems.retain(|arg| !options::is_option(arg));
 
     for problem in problems {
         run_problem(&problem);
@@ -0,0 +1,32 @@
+use std::env;
+
+// options follow the problem list on the command line, e.g.
+//   $ cargo run 2021:15 --tiles=3 --input=2021_15_example
+pub fn is_option(arg: &str) -> bool {
+    arg.starts_with("--")
+}
+
+pub fn flag(name: &str) -> bool {
+    env::args().any(|arg| arg == format!("--{}", name))
+}
+
+pub fn value(name: &str) -> Option<String> {
+    let prefix = format!("--{}=", name);
+
+    env::args()
+        .find(|arg| arg.starts_with(&prefix))
+        .map(|arg| String::from(&arg[prefix.len()..]))
+}
+
+pub fn parsed<T: std::str::FromStr>(name: &str, default: T) -> T {
+    match value(name) {
+        None => default,
+        Some(text) => text.parse()
+            .unwrap_or_else(|_| panic!("couldn't parse value '{}' for --{}", text, name)),
+    }
+}
+
+// lets a run swap the puzzle input for another file in input/, like the example from the puzzle text
+pub fn input_name(default: &str) -> String {
+    value("input").unwrap_or_else(|| String::from(default))
+}
@@ -0,0 +1,187 @@
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap};
+use std::hash::Hash;
+
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct Point(pub usize, pub usize);
+
+#[derive(Debug)]
+pub struct Grid {
+    pub width: usize,
+    pub height: usize,
+    cells: Vec<u32>,
+}
+
+#[derive(Debug)]
+pub struct Path {
+    pub cost: u32,
+    pub points: Vec<Point>,
+}
+
+// cheapest way through any graph, every state along it and the steps taken in between,
+// so `steps[i]` leads from `states[i]` to `states[i + 1]`
+#[derive(Debug)]
+pub struct Route<S, E> {
+    pub cost: u32,
+    pub states: Vec<S>,
+    pub steps: Vec<E>,
+}
+
+// entry in the open set, ordered so that BinaryHeap pops the lowest estimate first
+#[derive(Copy, Clone, Eq, PartialEq)]
+struct Frontier<S> {
+    estimate: u32,
+    cost: u32,
+    state: S,
+}
+
+impl<S: Eq> Ord for Frontier<S> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.estimate.cmp(&self.estimate)
+            .then_with(|| other.cost.cmp(&self.cost))
+    }
+}
+
+impl<S: Eq> PartialOrd for Frontier<S> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Point {
+    pub fn manhattan(&self, other: &Point) -> u32 {
+        let Point(x1, y1) = *self;
+        let Point(x2, y2) = *other;
+
+        (x1.abs_diff(x2) + y1.abs_diff(y2)) as u32
+    }
+}
+
+impl Grid {
+    pub fn new(width: usize, height: usize, cells: Vec<u32>) -> Grid {
+        if cells.len() != width * height {
+            panic!("grid cells don't match dimensions {}x{}", width, height);
+        }
+
+        Grid { width, height, cells }
+    }
+
+    pub fn get(&self, point: Point) -> u32 {
+        let Point(x, y) = point;
+        self.cells[y * self.width + x]
+    }
+
+    pub fn contains(&self, point: Point) -> bool {
+        let Point(x, y) = point;
+        x < self.width && y < self.height
+    }
+
+    pub fn bottom_right(&self) -> Point {
+        Point(self.width - 1, self.height - 1)
+    }
+
+    pub fn neighbors(&self, point: Point) -> Vec<Point> {
+        let Point(x, y) = point;
+        let mut neighbors = Vec::with_capacity(4);
+
+        if x + 1 < self.width  { neighbors.push(Point(x + 1, y)); }
+        if x > 0               { neighbors.push(Point(x - 1, y)); }
+        if y + 1 < self.height { neighbors.push(Point(x, y + 1)); }
+        if y > 0               { neighbors.push(Point(x, y - 1)); }
+
+        neighbors
+    }
+
+    fn min_cost(&self) -> u32 {
+        self.cells.iter().copied().min().unwrap_or(0)
+    }
+}
+
+// cost of a path is the sum of the cells entered, the start cell is free
+pub fn dijkstra(grid: &Grid, start: Point, goal: Point) -> Option<Path> {
+    grid_search(grid, start, goal, |_| 0)
+}
+
+// manhattan distance scaled by the cheapest cell never overestimates, so the result is still optimal
+pub fn a_star(grid: &Grid, start: Point, goal: Point) -> Option<Path> {
+    let step_cost = grid.min_cost();
+    grid_search(grid, start, goal, |point| point.manhattan(&goal) * step_cost)
+}
+
+fn grid_search<H>(grid: &Grid, start: Point, goal: Point, heuristic: H) -> Option<Path>
+    where H: Fn(&Point) -> u32
+{
+    if !grid.contains(start) || !grid.contains(goal) {
+        return None;
+    }
+
+    let neighbors = |point: &Point| grid.neighbors(*point).into_iter()
+        .map(|neighbor| (neighbor, grid.get(neighbor), ()))
+        .collect();
+    let route = search(start, |point| *point == goal, neighbors, heuristic)?;
+
+    Some(Path { cost: route.cost, points: route.states })
+}
+
+// A* over any graph. `successors` gives each state reachable in one step along with what
+// the step costs and whatever should be remembered about it. a heuristic of zero makes
+// this plain Dijkstra, otherwise it must never overestimate for the route to be cheapest
+pub fn search<S, E, G, F, H>(start: S, is_goal: G, mut successors: F, heuristic: H) -> Option<Route<S, E>>
+    where S: Clone + Eq + Hash,
+          G: Fn(&S) -> bool,
+          F: FnMut(&S) -> Vec<(S, u32, E)>,
+          H: Fn(&S) -> u32,
+{
+    let mut lowest_costs: HashMap<S, u32> = HashMap::new();
+    let mut came_from: HashMap<S, (S, E)> = HashMap::new();
+    let mut open: BinaryHeap<Frontier<S>> = BinaryHeap::new();
+
+    lowest_costs.insert(start.clone(), 0);
+    open.push(Frontier { estimate: heuristic(&start), cost: 0, state: start.clone() });
+
+    while let Some(Frontier { cost, state, .. }) = open.pop() {
+        if cost > lowest_costs[&state] {
+            continue; // stale entry, a cheaper route was already expanded
+        }
+
+        if is_goal(&state) {
+            let (states, steps) = walk_back(came_from, start, state);
+            return Some(Route { cost, states, steps });
+        }
+
+        for (next, step_cost, step) in successors(&state) {
+            let next_cost = cost + step_cost;
+
+            if lowest_costs.get(&next).is_none_or(|&lowest| next_cost < lowest) {
+                lowest_costs.insert(next.clone(), next_cost);
+                open.push(Frontier {
+                    estimate: next_cost + heuristic(&next),
+                    cost: next_cost,
+                    state: next.clone(),
+                });
+                came_from.insert(next, (state.clone(), step));
+            }
+        }
+    }
+
+    None
+}
+
+fn walk_back<S, E>(mut came_from: HashMap<S, (S, E)>, start: S, goal: S) -> (Vec<S>, Vec<E>)
+    where S: Clone + Eq + Hash
+{
+    let mut states = vec![goal.clone()];
+    let mut steps = Vec::new();
+    let mut current = goal;
+
+    while current != start {
+        let (previous, step) = came_from.remove(&current).unwrap();
+        steps.push(step);
+        states.push(previous.clone());
+        current = previous;
+    }
+
+    states.reverse();
+    steps.reverse();
+    (states, steps)
+}
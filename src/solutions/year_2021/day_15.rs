@@ -1,75 +1,154 @@
-use crate::input;
-use std::collections::{HashMap,VecDeque};
-
-const LOW_BOUND: usize = 0;
-const HIGH_BOUND: usize = 500;
-
-#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
-struct Point(usize, usize);
-
-impl Point {
-    fn from(x: i32, y: i32) -> Option<Point> {
-        if x >= LOW_BOUND as i32 && y >= LOW_BOUND as i32 &&
-           x < HIGH_BOUND as i32 && y < HIGH_BOUND as i32 {
-            Some(Point(x as usize, y as usize))
-        } else {
-            None
+use crate::{input, options};
+use crate::pathfinding::{self, Grid, Path, Point};
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+
+const DEFAULT_TILES: usize = 5;
+
+const ANSI_PATH: &str = "\x1b[1;31m";
+const ANSI_OTHER: &str = "\x1b[2m";
+const ANSI_RESET: &str = "\x1b[0m";
+
+// smallest side of a rendered image, small caves get blown up so they're still visible
+const MIN_IMAGE_SIZE: usize = 500;
+
+pub fn run() {
+    let tiles = options::parsed("tiles", DEFAULT_TILES);
+    let tile = parse_input();
+    let cave = tile_cave(&tile, tiles);
+
+    // part 1 only knows about the first tile, so its path can't wander into the others
+    let part_1 = pathfinding::dijkstra(&tile, Point(0, 0), tile.bottom_right())
+        .expect("no path to the bottom right of the first tile");
+    let part_2 = pathfinding::a_star(&cave, Point(0, 0), cave.bottom_right())
+        .expect("no path to the bottom right of the full cave");
+
+    println!(" part 1: {} ({} steps)", part_1.cost, part_1.points.len() - 1);
+    println!(" part 2: {} ({} steps)", part_2.cost, part_2.points.len() - 1);
+
+    if let Some(format) = options::value("render") {
+        render(&tile, &part_1, 1, &format);
+        render(&cave, &part_2, 2, &format);
+    }
+}
+
+// --render=ansi prints the cave to the terminal, --render=ppm writes an image per part
+fn render(cave: &Grid, path: &Path, part: u32, format: &str) {
+    match format {
+        "ansi" => {
+            println!("\n part {} path:", part);
+            print!("{}", render_ansi(cave, path));
+        },
+        "ppm" => {
+            let filename = format!("2021_15_part_{}.ppm", part);
+            write_ppm(cave, path, &filename).expect("couldn't write image");
+            println!(" part {} path written to {}", part, filename);
+        },
+        _ => println!(" unknown render format '{}', expected ansi or ppm", format),
+    }
+}
+
+// risks as digits, one row per line, with the path in bold red and everything else dimmed
+fn render_ansi(cave: &Grid, path: &Path) -> String {
+    let on_path: HashSet<&Point> = path.points.iter().collect();
+    let mut output = String::new();
+
+    for y in 0..cave.height {
+        for x in 0..cave.width {
+            let point = Point(x, y);
+            let color = if on_path.contains(&point) { ANSI_PATH } else { ANSI_OTHER };
+
+            output.push_str(&format!("{}{}", color, cave.get(point)));
         }
+        output.push_str(ANSI_RESET);
+        output.push('\n');
     }
 
-    fn to_coord(&self) -> (i32, i32) {
-        let Point(x, y) = self;
-        (*x as i32, *y as i32)
+    output
+}
+
+// binary PPM (P6) where risk is shaded from dark to light and the path is drawn in red
+fn write_ppm(cave: &Grid, path: &Path, filename: &str) -> io::Result<()> {
+    let on_path: HashSet<&Point> = path.points.iter().collect();
+    let scale = usize::max(1, MIN_IMAGE_SIZE / usize::max(cave.width, cave.height));
+    let max_risk = (0..cave.height)
+        .flat_map(|y| (0..cave.width).map(move |x| Point(x, y)))
+        .map(|point| cave.get(point))
+        .fold(1, u32::max);
+
+    let mut out = BufWriter::new(File::create(filename)?);
+    write!(out, "P6\n{} {}\n255\n", cave.width * scale, cave.height * scale)?;
+
+    for y in 0..cave.height * scale {
+        for x in 0..cave.width * scale {
+            let point = Point(x / scale, y / scale);
+
+            let pixel = if on_path.contains(&point) {
+                [255, 0, 0]
+            } else {
+                let shade = (40 + cave.get(point) * 180 / max_risk) as u8;
+                [shade, shade, shade]
+            };
+
+            out.write_all(&pixel)?;
+        }
     }
+
+    out.flush()
 }
 
-#[derive(Debug)]
-struct Path(Point, u32);
+// the cave as scanned, a single tile
+fn parse_input() -> Grid {
+    let lines: Vec<String> = input::read_lines(&options::input_name("2021_15"))
+        .expect("Couldn't read input")
+        .into_iter()
+        .filter(|line| !line.trim().is_empty())
+        .collect();
+
+    let tile_height = lines.len();
+    let tile_width = lines.first().map(|line| line.trim().len()).unwrap_or(0);
+    if tile_width == 0 {
+        panic!("cave input is empty");
+    }
 
-pub fn run() {
-    let mut minimum_paths: HashMap<Point, u32> = HashMap::new();
-    let mut path_queue: VecDeque<Path> = VecDeque::new();
-    let mut cave = [[0u32; HIGH_BOUND]; HIGH_BOUND];
-    parse_input(&mut cave);
-
-    path_queue.push_back(Path(Point(0, 0), 0));
-    minimum_paths.insert(Point(0, 0), 0);
-
-    while let Some(Path(position, path_risk)) = path_queue.pop_front() {
-        if match minimum_paths.get(&position) { None => true, Some(&lowest) => path_risk <= lowest } {
- 
-            for neighbor in neighbors(&position) {
-                if let Some(point) = neighbor {
-                    let Point(x, y) = point;
-                    let risk = path_risk + cave[y][x];
-        
-                    if match minimum_paths.get(&point) { None => true, Some(&lowest) => risk < lowest } {
-                        minimum_paths.insert(point, risk);
-                        path_queue.push_back(Path(point, risk));
-                    }
-                }
-            }
+    let mut cells = Vec::with_capacity(tile_width * tile_height);
+
+    for (y, line) in lines.iter().enumerate() {
+        let row: Vec<u32> = line.trim().chars()
+            .map(|ch| ch.to_digit(10).expect("cave risk must be a digit"))
+            .collect();
+
+        if row.len() != tile_width {
+            panic!("cave row {} has {} columns, expected {}", y, row.len(), tile_width);
         }
+        cells.extend(row);
     }
 
-    println!(" part 1: {}", minimum_paths.get(&Point(99, 99)).unwrap());
-    println!(" part 2: {}", minimum_paths.get(&Point(499, 499)).unwrap());
+    Grid::new(tile_width, tile_height, cells)
 }
 
-fn parse_input(cave: &mut [[u32; HIGH_BOUND]; HIGH_BOUND]) {
-    let lines = input::read_lines("2021_15")
-        .expect("Couldn't read input");
+// the full cave, `tile` repeated `tiles` times in each direction with the risk going up by
+// one for every tile further right or down
+fn tile_cave(tile: &Grid, tiles: usize) -> Grid {
+    if tiles == 0 {
+        panic!("cave needs at least one tile");
+    }
 
-    for y in 0..lines.len() {
-        for x in 0..lines[y].len() {
+    let (tile_width, tile_height) = (tile.width, tile.height);
+    let width = tile_width * tiles;
+    let height = tile_height * tiles;
+    let mut cave = vec![0u32; width * height];
 
-            let mut row_base_risk = lines[y][x..x+1].parse().unwrap();
+    for y in 0..tile_height {
+        for x in 0..tile_width {
+            let mut row_base_risk = tile.get(Point(x, y));
 
-            for tile_y in 0..5 {
+            for tile_y in 0..tiles {
                 let mut base_risk = row_base_risk;
 
-                for tile_x in 0..5 {
-                    cave[y + tile_y * 100][x + tile_x * 100] = base_risk;
+                for tile_x in 0..tiles {
+                    cave[(y + tile_y * tile_height) * width + x + tile_x * tile_width] = base_risk;
 
                     rollover_risk(&mut base_risk);
                 }
@@ -78,6 +157,8 @@ fn parse_input(cave: &mut [[u32; HIGH_BOUND]; HIGH_BOUND]) {
             }
         }
     }
+
+    Grid::new(width, height, cave)
 }
 
 fn rollover_risk(risk: &mut u32) {
@@ -86,14 +167,3 @@ fn rollover_risk(risk: &mut u32) {
         *risk = 1;
     }
 }
-
-fn neighbors(point: &Point) -> Vec<Option<Point>> {
-    let (x, y) = point.to_coord();
-
-    vec![
-        Point::from(x+1, y),
-        Point::from(x-1, y),
-        Point::from(x, y+1),
-        Point::from(x, y-1),
-    ]
-}
@@ -1,85 +1,106 @@
-// target area: x=57..116, y=-198..-148
+use crate::{input, options};
+use crate::ballistics::{self, Axis, Region};
+use std::{cmp, collections::HashSet};
 
-#[derive(Debug)]
-enum Velocity {
-    Bounded(i32, usize, usize), /* (vel, start, stop) */
-    Unbounded(i32, usize),      /* (vel, start) */
-}
+// --drag and --gravity change how fast the probe slows down on each axis
+pub fn run() {
+    let target = parse_input();
+    let x_axis = Axis::new_drag(options::parsed("drag", 1));
+    let y_axis = Axis::new_gravity(options::parsed("gravity", 1));
 
-impl Velocity {
-    fn overlaps(&self, other: &Self) -> bool {
-        use Velocity::*;
-
-        match self {
-            Bounded(_, lstart, lstop) => match other {
-                Bounded(_, rstart, rstop) => lstop >= rstart && rstop >= lstart,
-                Unbounded(_, rstart) => rstart <= lstop,
-            },
-            Unbounded(_, lstart) => match other {
-                Bounded(_, _, rstop) => lstart <= rstop,
-                Unbounded(_, _) => true,
-            },
-        }
+    if ballistics::infinitely_many_hits(x_axis, y_axis, &target) {
+        println!(" part 1: no highest apex, the target spans y=0");
+        println!(" part 2: infinitely many velocities, the target spans y=0");
+        return;
     }
-}
 
-fn step_to_target(velocity: i32, min: i32, max: i32, stop_at_zero: bool) -> Option<Velocity> {
-    let mut pos: i32 = 0;
-    let mut vel: i32 = velocity;
-    let mut steps: usize = 0;
-    let mut start: usize = 0;
-
-    loop {
-        steps += 1;
-        pos += vel;
-        if !stop_at_zero || vel > 0 {
-            vel -= 1;
-        }
-        
-        if pos >= min && pos <= max {
-            if start == 0 {
-                start = steps;
-            }
-        } else if start > 0 {
-            return Some(Velocity::Bounded(velocity, start, steps - 1));
-        } else if 0 < max && max < pos || 0 > min && min > pos {
-            return None;
-        }
+    let on_target = ballistics::hits(x_axis, y_axis, &target);
+    let highest = on_target.iter()
+        .max_by_key(|&&(dx, dy)| (y_axis.apex(dy), -dx.abs()));
 
-        if stop_at_zero && vel == 0 {
-            if start > 0 {
-                return Some(Velocity::Unbounded(velocity, start));
-            } else {
-                return None;
-            }
+    match highest {
+        Some(&(dx, dy)) => println!(" part 1: {} (from {},{})", y_axis.apex(dy), dx, dy),
+        None => println!(" part 1: the target can't be reached"),
+    }
+    println!(" part 2: {}", on_target.len());
+
+    if options::flag("velocities") {
+        for (dx, dy) in on_target.iter() {
+            println!("{},{}", dx, dy);
         }
     }
+
+    if let Some(shot) = options::value("trajectory") {
+        let (dx, dy) = parse_velocity(&shot);
+        draw_trajectory(x_axis, y_axis, &target, dx, dy);
+    }
 }
 
-pub fn run() {
-    let mut x_vels: Vec<Velocity> = Vec::new();
-    let mut y_vels: Vec<Velocity> = Vec::new();
-    let mut on_target: Vec<(&Velocity, &Velocity)> = Vec::new();
+// same legend as the puzzle, S is the launch point, # the probe and T the target area
+fn draw_trajectory(x_axis: Axis, y_axis: Axis, target: &Region, dx: i32, dy: i32) {
+    let positions = ballistics::trajectory(x_axis, y_axis, target, dx, dy);
+    let hit = positions.last().is_some_and(|&(x, y)| target.contains(x, y));
+    let path: HashSet<&(i32, i32)> = positions.iter().collect();
 
-    for x in 11..117 {
-        if let Some(vel) = step_to_target(x, 57, 116, true) {
-            x_vels.push(vel);
-        }
+    let all_x = positions.iter().map(|&(x, _)| x).chain([0, target.x_min, target.x_max]);
+    let all_y = positions.iter().map(|&(_, y)| y).chain([0, target.y_min, target.y_max]);
+    let (left, right) = (all_x.clone().min().unwrap(), all_x.max().unwrap());
+    let (bottom, top) = (all_y.clone().min().unwrap(), all_y.max().unwrap());
+
+    println!("\n {},{} {} the target", dx, dy, if hit { "hits" } else { "misses" });
+    for y in (bottom..=top).rev() {
+        let row: String = (left..=right)
+            .map(|x| {
+                if x == 0 && y == 0 { 'S' }
+                else if path.contains(&(x, y)) { '#' }
+                else if target.contains(x, y) { 'T' }
+                else { '.' }
+            })
+            .collect();
+
+        println!("{}", row);
     }
+}
 
-    for y in -198..198 {
-        if let Some(vel) = step_to_target(y, -198, -148, false) {
-            y_vels.push(vel);
-        }
+// input format:
+//   6,3
+fn parse_velocity(input: &str) -> (i32, i32) {
+    let parts = input.split(",").collect::<Vec<&str>>();
+    if parts.len() != 2 {
+        panic!("expected a velocity like --trajectory=6,3");
     }
 
-    for dx in x_vels.iter() {
-        for dy in y_vels.iter() {
-            if dx.overlaps(&dy) {
-                on_target.push((dx, dy));
-            }
-        }
+    (
+        parts[0].parse().expect("parse error"),
+        parts[1].parse().expect("parse error"),
+    )
+}
+
+// input format:
+//   target area: x=57..116, y=-198..-148
+fn parse_input() -> Region {
+    let text = input::read_all(&options::input_name("2021_17"))
+        .expect("couldn't read input");
+    let ranges = text.trim()
+        .trim_start_matches("target area: ")
+        .split(", ")
+        .collect::<Vec<&str>>();
+
+    let (x_min, x_max) = parse_range(ranges[0]);
+    let (y_min, y_max) = parse_range(ranges[1]);
+
+    Region {
+        x_min, x_max,
+        y_min, y_max,
     }
+}
 
-    println!(" part 2: {}", on_target.len());
-}
\ No newline at end of file
+// input format:
+//   x=57..116
+fn parse_range(input: &str) -> (i32, i32) {
+    let parts = input[2..].split("..").collect::<Vec<&str>>();
+    let first: i32 = parts[0].parse().expect("parse error");
+    let second: i32 = parts[1].parse().expect("parse error");
+
+    (cmp::min(first, second), cmp::max(first, second))
+}
@@ -1,444 +1,460 @@
-use std::ops::{Sub,Not,Add};
-use std::cmp::{Ordering,PartialOrd};
-use crate::input;
-
-#[derive(Copy, Clone, PartialEq, Debug)]
-struct Point {
-    x: i32,
-    y: i32,
-    z: i32,
-}
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use crate::{input, options};
+use crate::geometry::{Point, Rotation, Transform};
 
-#[derive(Copy, Clone, Debug)]
-struct PointVector {
-    len: i32,
-    vec: Point,
-    identity: [i32; 3],
-    points: [Point; 2],
-}
+const DEFAULT_OVERLAP: usize = 12;
+
+// the absolute differences between two beacons along each axis, sorted. every rotation
+// only permutes and flips the axes, so both scanners see the same fingerprint for a pair
+type Fingerprint = [i32; 3];
 
 #[derive(Debug)]
 struct Scanner {
     name: String,
     beacons: Vec<Point>,
-    identity: Vec<PointVector>,
-    matches_with_world: usize,
+    pairs: HashMap<Fingerprint, Vec<(usize, usize)>>,
 }
 
-impl Not for Point {
-    type Output = Self;
-
-    fn not(self) -> Self::Output {
-        Point {
-            x: -self.x,
-            y: -self.y,
-            z: -self.z,
-        }
-    }
+// where a scanner ended up in scanner 0's frame, and the scanner whose beacons it lined up with
+#[derive(Debug)]
+struct Placement {
+    name: String,
+    transform: Transform,
+    matched_against: Option<usize>,
 }
 
-impl Add for Point {
-    type Output = Point;
+// lines scanners up two at a time, remembering the outcome for both directions
+struct Aligner<'s> {
+    scanners: &'s [Scanner],
+    overlap: usize,
+    cache: HashMap<(usize, usize), Option<Transform>>,
+}
 
-    fn add(self, other: Self) -> Self::Output {
-        Point {
-            x: self.x + other.x,
-            y: self.y + other.y,
-            z: self.z + other.z,
-        }
-    }
+#[derive(Debug)]
+enum AlignError {
+    Ambiguous { scanners: (String, String), fits: Vec<(Transform, usize)> }, /* more than one way to line them up */
+    Conflict { scanner: String, through: (String, String) }, /* two placed neighbours disagree */
+    Unplaced(Vec<String>), /* never lined up with anything */
 }
 
-impl Sub for Point {
-    type Output = Point;
+impl Scanner {
+    fn new(name: &str, beacons: Vec<Point>) -> Scanner {
+        let mut pairs: HashMap<Fingerprint, Vec<(usize, usize)>> = HashMap::new();
 
-    fn sub(self, other: Self) -> Self::Output {
-        Point {
-            x: self.x - other.x,
-            y: self.y - other.y,
-            z: self.z - other.z,
+        for j in 0..beacons.len() {
+            for k in (j + 1)..beacons.len() {
+                pairs.entry(fingerprint(beacons[k] - beacons[j]))
+                    .or_default()
+                    .push((j, k));
+            }
         }
-    }
-}
 
-impl Ord for PointVector {
-    fn cmp(&self, other: &Self) -> Ordering {
-        if self > other {
-            Ordering::Greater
-        } else if self < other {
-            Ordering::Less
-        } else {
-            Ordering::Equal  
+        Scanner {
+            name: String::from(name),
+            beacons,
+            pairs,
         }
     }
 
-    fn max(self, other: Self) -> Self {
-        if other > self {
-            other
-        } else {
-            self
-        }
-    }
+    // enough shared fingerprints for `overlap` beacons to be seen by both
+    fn could_overlap(&self, other: &Scanner, overlap: usize) -> bool {
+        let shared: usize = self.pairs.iter()
+            .filter_map(|(fingerprint, own)| {
+                other.pairs.get(fingerprint).map(|theirs| own.len().min(theirs.len()))
+            })
+            .sum();
 
-    fn min(self, other: Self) -> Self {
-        if other < self {
-            other
-        } else {
-            self
-        }
+        shared >= pairs_between(overlap)
     }
 
-    fn clamp(self, min: Self, max: Self) -> Self {
-        if self < min {
-            min
-        } else if self > max {
-            max
-        } else {
-            self
+    // the transform taking `other`'s coordinates into this scanner's, if at least `overlap`
+    // beacons line up. every pair of beacons with a shared fingerprint votes for the rotation
+    // and translation that would lay one on the other. `overlap` beacons seen by both cast
+    // at least one vote per pair between them, so only transforms with that many are checked
+    fn align(&self, other: &Scanner, overlap: usize) -> Result<Option<Transform>, AlignError> {
+        let mut votes: HashMap<Transform, usize> = HashMap::new();
+
+        for (fingerprint, own_pairs) in self.pairs.iter() {
+            let other_pairs = match other.pairs.get(fingerprint) {
+                Some(pairs) => pairs,
+                None => continue,
+            };
+
+            for &(a1, a2) in own_pairs {
+                for &(b1, b2) in other_pairs {
+                    let from = other.beacons[b2] - other.beacons[b1];
+                    let to = self.beacons[a2] - self.beacons[a1];
+
+                    for rotation in Rotation::rotate_to(from, to) {
+                        let translation = self.beacons[a1] - rotation.apply(other.beacons[b1]);
+                        *votes.entry(Transform::new(rotation, translation)).or_insert(0) += 1;
+                    }
+                    for rotation in Rotation::rotate_to(from, -to) {
+                        let translation = self.beacons[a2] - rotation.apply(other.beacons[b1]);
+                        *votes.entry(Transform::new(rotation, translation)).or_insert(0) += 1;
+                    }
+                }
+            }
         }
-    }
-}
 
-impl PartialOrd for PointVector {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(self.cmp(other))
-    }
-    // what a bunch of derp lol
-    fn lt(&self, other: &Self) -> bool {
-        if self.len < other.len {
-            true
-        } else if self.len > other.len {
-            false
-        } else if self.identity[0] < other.identity[0] {
-            true
-        } else if self.identity[0] > other.identity[0] {
-            false
-        } else if self.identity[1] < other.identity[1] {
-            true
-        } else if self.identity[1] > other.identity[1] {
-            false
-        } else if self.identity[2] < other.identity[2] {
-            true
-        } else if self.identity[2] > other.identity[2] {
-            false
-        } else {
-            false
+        let own: HashSet<Point> = self.beacons.iter().copied().collect();
+        let mut fits: Vec<(Transform, usize)> = votes.into_iter()
+            .filter(|&(_, count)| count >= pairs_between(overlap))
+            .map(|(transform, _)| {
+                let shared = other.beacons.iter()
+                    .filter(|&&beacon| own.contains(&transform.apply(beacon)))
+                    .count();
+
+                (transform, shared)
+            })
+            .filter(|&(_, shared)| shared >= overlap)
+            .collect();
+        fits.sort_by_key(|&(transform, shared)| (Reverse(shared), transform.translation));
+
+        match fits.len() {
+            0 => Ok(None),
+            1 => Ok(Some(fits[0].0)),
+            _ => Err(AlignError::Ambiguous {
+                scanners: (self.name.clone(), other.name.clone()),
+                fits,
+            }),
         }
     }
-    fn le(&self, other: &Self) -> bool {
-        self == other || self < other
-    }
-    fn gt(&self, other: &Self) -> bool {
-        self != other && !(self < other)
-    }
-    fn ge(&self, other: &Self) -> bool {
-        !(self < other)
-    }
-}
-
-impl PartialEq for PointVector {
-    fn eq(&self, other: &Self) -> bool {
-        self.identity[0] == other.identity[0] &&
-        self.identity[1] == other.identity[1] &&
-        self.identity[2] == other.identity[2]
-    }
 }
-impl Eq for PointVector {}
 
-impl PointVector {
-    fn from_subtract(left: Point, right: Point) -> Self {
-        let points = [left, right];
-        let vec = left - right;
-
-        let mut identity = [vec.x.abs(), vec.y.abs(), vec.z.abs()];
-        identity.sort();
-
-        let len = identity[0] + identity[1] + identity[2];
-
-        PointVector {
-            len,
-            vec,
-            identity,
-            points,
+impl<'s> Aligner<'s> {
+    fn new(scanners: &'s [Scanner], overlap: usize) -> Aligner<'s> {
+        if overlap < 3 {
+            panic!("scanners need at least 3 beacons in common to pin down a rotation");
         }
-    }
-}
 
-impl Scanner {
-    fn new(name: &str, beacons: Vec<Point>) -> Scanner {
-        Scanner {
-            name: String::from(name),
-            identity: Self::calc_identity(&beacons),
-            matches_with_world: 0,
-            beacons,
+        Aligner {
+            scanners,
+            overlap,
+            cache: HashMap::new(),
         }
     }
 
-    fn calc_identity(beacons: &Vec<Point>) -> Vec<PointVector> {
-        let mut identity: Vec<PointVector> = Vec::new();
-        for j in 0..(beacons.len() - 1) {
-            for k in (j + 1)..beacons.len() {
-                identity.push(PointVector::from_subtract(beacons[j], beacons[k]));
-            }
+    // transform from scanner `to`'s coordinates into scanner `from`'s
+    fn alignment(&mut self, from: usize, to: usize) -> Result<Option<Transform>, AlignError> {
+        if let Some(&transform) = self.cache.get(&(from, to)) {
+            return Ok(transform);
         }
 
-        identity.sort();
+        let (left, right) = (&self.scanners[from], &self.scanners[to]);
+        let transform = if left.could_overlap(right, self.overlap) {
+            left.align(right, self.overlap)?
+        } else {
+            None
+        };
 
-        identity
+        self.cache.insert((from, to), transform);
+        self.cache.insert((to, from), transform.map(|t| t.inverse()));
+        Ok(transform)
     }
 
-    fn compare_to(&self, other: &Self) -> Vec<(PointVector, PointVector)> {
-        let mut matches: Vec<(PointVector, PointVector)> = Vec::new();
-
-        let mut left_vectors = self.identity.iter();
-        let mut left_current = left_vectors.next();
-
-        let mut right_vectors = other.identity.iter();
-        let mut right_current = right_vectors.next();
-
-        loop {
-            match both(left_current, right_current) {
-                None => return matches,
-                Some((&left, &right)) => {
-                    if left < right {
-                        left_current = left_vectors.next();
-                    } else if right < left {
-                        right_current = right_vectors.next();
-                    } else {
-                        matches.push( (left, right) );
+    // walks outward from scanner 0, placing every scanner that lines up with one that's
+    // already placed, so each scanner is only aligned with its neighbours in the tree.
+    // scanners that were placed through someone else are still lined up with the parent
+    // to make sure every overlap agrees on where they are
+    fn spanning_tree(&mut self) -> Result<Vec<Placement>, AlignError> {
+        let mut placements: Vec<Option<Placement>> = self.scanners.iter().map(|_| None).collect();
+        placements[0] = Some(Placement {
+            name: self.scanners[0].name.clone(),
+            transform: Transform::IDENTITY,
+            matched_against: None,
+        });
+        let mut queue: VecDeque<usize> = VecDeque::from([0]);
+
+        while let Some(parent) = queue.pop_front() {
+            let parent_transform = placements[parent].as_ref().unwrap().transform;
+
+            for (child, placement) in placements.iter_mut().enumerate() {
+                if child == parent {
+                    continue;
+                }
 
-                        left_current = left_vectors.next();
-                        right_current = right_vectors.next();
+                let transform = match self.alignment(parent, child)? {
+                    Some(transform) => parent_transform.compose(&transform),
+                    None => continue,
+                };
+
+                if let Some(placed) = placement.as_ref() {
+                    if placed.transform != transform {
+                        let through = placed.matched_against.unwrap_or(0);
+                        return Err(AlignError::Conflict {
+                            scanner: placed.name.clone(),
+                            through: (self.scanners[through].name.clone(), self.scanners[parent].name.clone()),
+                        });
                     }
+                } else {
+                    *placement = Some(Placement {
+                        name: self.scanners[child].name.clone(),
+                        transform,
+                        matched_against: Some(parent),
+                    });
+                    queue.push_back(child);
                 }
             }
         }
-    }
 
-    fn add_unique_beacons(&mut self, scanner: &Scanner, transformer: Box<dyn Transformer>) {
-        let mut unique_beacons: Vec<Point> = Vec::new();
+        let lost: Vec<String> = self.scanners.iter().zip(placements.iter())
+            .filter(|(_, placement)| placement.is_none())
+            .map(|(scanner, _)| scanner.name.clone())
+            .collect();
+        if !lost.is_empty() {
+            return Err(AlignError::Unplaced(lost));
+        }
 
-        for &beacon in scanner.beacons.iter() {
-            let world_beacon_coord = transformer.transform(beacon);
-            let mut should_add = true;
+        Ok(placements.into_iter().map(Option::unwrap).collect())
+    }
+}
 
-            for &world_beacon in self.beacons.iter() {
-                if world_beacon == world_beacon_coord {
-                    should_add = false;
-                    break;
+impl fmt::Display for AlignError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AlignError::Ambiguous { scanners, fits } => {
+                write!(f, "{} lines up with {} in {} ways:", scanners.1, scanners.0, fits.len())?;
+                for (transform, shared) in fits {
+                    write!(f, "\n   {} beacons shared {}", shared, describe(transform))?;
                 }
-            }
+                Ok(())
+            },
+            AlignError::Conflict { scanner, through } =>
+                write!(f, "{} ends up in different places through {} and through {}",
+                       scanner, through.0, through.1),
+            AlignError::Unplaced(names) =>
+                write!(f, "couldn't line up {} with the rest", names.join(", ")),
+        }
+    }
+}
+
+impl std::error::Error for AlignError {}
 
-            if should_add {
-                unique_beacons.push(world_beacon_coord);
+// --overlap sets how many beacons two scanners must both see to count as lined up
+pub fn run() {
+    let scanners: Vec<Scanner> = get_input();
+    let overlap = options::parsed("overlap", DEFAULT_OVERLAP);
+    let placements = match Aligner::new(&scanners, overlap).spanning_tree() {
+        Ok(placements) => placements,
+        Err(error) => return println!("  Alignment failed: {}", error),
+    };
+
+    // each beacon along with the lowest numbered scanner that saw it
+    let mut seen: HashSet<Point> = HashSet::new();
+    let mut beacons: Vec<(Point, usize)> = Vec::new();
+    for (index, (scanner, placement)) in scanners.iter().zip(placements.iter()).enumerate() {
+        for beacon in placement.transform.apply_all(&scanner.beacons) {
+            if seen.insert(beacon) {
+                beacons.push((beacon, index));
             }
         }
+    }
 
-        for beacon in unique_beacons {
-            for &other in self.beacons.iter() {
-                self.identity.push(PointVector::from_subtract(beacon, other));
+    println!("  Part 1: {} beacons", beacons.len());
+
+    let mut max = (0, &placements[0].name, &placements[0].name);
+    for s1 in placements.iter() {
+        for s2 in placements.iter() {
+            let len = s1.transform.translation.manhattan(&s2.transform.translation);
+            if len > max.0 {
+                max = (len, &s1.name, &s2.name);
             }
-            self.beacons.push(beacon);
         }
-
-        self.identity.sort();
     }
-}
 
-trait Transformer {
-    fn transform(&self, point: Point) -> Point;
-    fn transform_all(&self, points: &Vec<Point>) -> Vec<Point> {
-        points.iter().map(|&p| self.transform(p)).collect::<Vec<Point>>()
+    println!("  Part 2: {} units, between {} and {}", max.0, max.1, max.2);
+
+    if let Some(format) = options::value("report") {
+        report(&placements, &format);
     }
-}
 
-struct Rotation {
-    x: fn(Point) -> i32,
-    y: fn(Point) -> i32,
-    z: fn(Point) -> i32,
+    if let Some(format) = options::value("cloud") {
+        cloud(&beacons, &placements, &format);
+    }
 }
 
-impl Transformer for Rotation {
-    fn transform(&self, p: Point) -> Point {
-        Point {
-            x: (self.x)(p),
-            y: (self.y)(p),
-            z: (self.z)(p),
-        }
+// --cloud=ply or obj writes the beacons as a point cloud, one color per scanner, with the
+// scanners themselves in white
+fn cloud(beacons: &[(Point, usize)], placements: &[Placement], format: &str) {
+    let mut points: Vec<(Point, [u8; 3])> = beacons.iter()
+        .map(|&(beacon, scanner)| (beacon, scanner_color(scanner)))
+        .collect();
+    points.extend(placements.iter().map(|placement| (placement.transform.translation, [255, 255, 255])));
+
+    let filename = format!("2021_19_beacons.{}", format);
+    let written = match format {
+        "ply" => write_ply(&points, &filename),
+        "obj" => write_obj(&points, &filename),
+        _ => return println!(" unknown cloud format '{}', expected ply or obj", format),
+    };
+
+    match written {
+        Ok(()) => println!(" point cloud written to {}", filename),
+        Err(error) => println!(" couldn't write point cloud, {}", error),
     }
 }
 
-impl Rotation {
-    fn rotate_to(from: Point, to: Point) -> Box<dyn Transformer> {
-        Box::new(Rotation {
-            x: Self::transform_accessor(to.x, from),
-            y: Self::transform_accessor(to.y, from),
-            z: Self::transform_accessor(to.z, from),
-        })
-    }
+fn write_ply(points: &[(Point, [u8; 3])], filename: &str) -> io::Result<()> {
+    let mut out = BufWriter::new(File::create(filename)?);
+    writeln!(out, "ply\nformat ascii 1.0\nelement vertex {}", points.len())?;
+    writeln!(out, "property int x\nproperty int y\nproperty int z")?;
+    writeln!(out, "property uchar red\nproperty uchar green\nproperty uchar blue\nend_header")?;
 
-    fn transform_accessor(val: i32, point: Point) -> fn(Point) -> i32 {
-             if val ==  point.x { |p|  p.x }
-        else if val == -point.x { |p| -p.x }
-        else if val ==  point.y { |p|  p.y }
-        else if val == -point.y { |p| -p.y }
-        else if val ==  point.z { |p|  p.z }
-        else if val == -point.z { |p| -p.z }
-        else {
-            panic!("couldn't match target val to point");
-        }
+    for (point, [r, g, b]) in points {
+        writeln!(out, "{} {} {} {} {} {}", point.x, point.y, point.z, r, g, b)?;
     }
-}
 
-struct Translation {
-    vector: Point
+    out.flush()
 }
 
-impl Transformer for Translation {
-    fn transform(&self, p: Point) -> Point {
-        p + self.vector
-    }
-}
+// plain obj has no colors, but most viewers read them as three extra vertex fields from 0 to 1
+fn write_obj(points: &[(Point, [u8; 3])], filename: &str) -> io::Result<()> {
+    let mut out = BufWriter::new(File::create(filename)?);
 
-impl Translation {
-    fn translate_to(vector: Point) -> Box<dyn Transformer> {
-        Box::new(Translation {
-            vector
-        })
+    for (point, color) in points {
+        let [r, g, b] = color.map(|channel| channel as f64 / 255.0);
+        writeln!(out, "v {} {} {} {:.3} {:.3} {:.3}", point.x, point.y, point.z, r, g, b)?;
     }
-}
 
-struct TransformerChain {
-    transformers: Vec<Box<dyn Transformer>>
+    out.flush()
 }
 
-impl Transformer for TransformerChain {
-    fn transform(&self, p: Point) -> Point {
-        let mut transformed = p;
-        for t in self.transformers.iter() {
-            transformed = t.transform(transformed);
-        }
+// spreads the scanners around the color wheel, stepping by the golden angle so
+// neighbouring scanners never get similar hues
+fn scanner_color(scanner: usize) -> [u8; 3] {
+    let hue = (scanner as f64 * 137.508) % 360.0 / 60.0;
+    let fade = 1.0 - (hue % 2.0 - 1.0).abs();
+
+    let (r, g, b) = match hue as u32 {
+        0 => (1.0, fade, 0.0),
+        1 => (fade, 1.0, 0.0),
+        2 => (0.0, 1.0, fade),
+        3 => (0.0, fade, 1.0),
+        4 => (fade, 0.0, 1.0),
+        _ => (1.0, 0.0, fade),
+    };
+
+    [(r * 255.0) as u8, (g * 255.0) as u8, (b * 255.0) as u8]
+}
 
-        transformed
+// --report=text prints every scanner's placement, --report=json or csv writes it to a file
+fn report(placements: &[Placement], format: &str) {
+    let written = match format {
+        "text" => {
+            print_report(placements);
+            return;
+        },
+        "json" => write_json(placements, "2021_19_scanners.json"),
+        "csv" => write_csv(placements, "2021_19_scanners.csv"),
+        _ => return println!(" unknown report format '{}', expected text, json or csv", format),
+    };
+
+    match written {
+        Ok(()) => println!(" scanner report written to 2021_19_scanners.{}", format),
+        Err(error) => println!(" couldn't write scanner report, {}", error),
     }
 }
 
-impl TransformerChain {
-    fn new(transformers: Vec<Box<dyn Transformer>>) -> Box<Self> {
-        Box::new(Self { transformers })
+fn print_report(placements: &[Placement]) {
+    println!();
+    for placement in placements {
+        print!("  {:<12} {}", placement.name, describe(&placement.transform));
+        match placement.matched_against {
+            Some(parent) => println!(", matched against {}", placements[parent].name),
+            None => println!(", reference frame"),
+        }
     }
 }
 
-pub fn run() {
-    let mut scanners: Vec<Scanner> = get_input();
-    let mut global_scanner = scanners.remove(0);
-    let mut scanner_locs: Vec<(Point, String)> = vec![
-        (Point { x: 0, y: 0, z: 0 }, String::from(&global_scanner.name))
-    ];
-
-    while scanners.len() > 0 {
-        sort_scanners(&global_scanner, &mut scanners);
-
-        let scanner = scanners.remove(0);
-        let mut matches = scanner.compare_to(&global_scanner);
-        let m1 = matches.remove(0);
-        let m2 = matches.remove(0);
-        let mut rotate = Rotation::rotate_to(m1.0.vec, m1.1.vec);
-        let s_points = vec![m1.0.points[0], m1.0.points[1], m2.0.points[0], m2.0.points[1]];
-        let w_points = vec![m1.1.points[0], m1.1.points[1], m2.1.points[0], m2.1.points[1]];
-
-        let scanner_location = match scanner_location(&w_points, rotate.transform_all(&s_points)) {
-            Some(location) => location,
-            None => {
-                rotate = Rotation::rotate_to(m1.0.vec, !m1.1.vec);
-
-                match scanner_location(&w_points, rotate.transform_all(&s_points)) {
-                    Some(location) => location,
-                    None => panic!("Couldn't establish rotation")
-                }
-            }
+fn write_json(placements: &[Placement], filename: &str) -> io::Result<()> {
+    let mut out = BufWriter::new(File::create(filename)?);
+    writeln!(out, "[")?;
+
+    for (index, placement) in placements.iter().enumerate() {
+        let position = placement.transform.translation;
+        let offset = relative_offset(placements, placement);
+        let rows: Vec<String> = placement.transform.rotation.matrix().iter()
+            .map(|row| format!("[{}, {}, {}]", row[0], row[1], row[2]))
+            .collect();
+        let matched_against = match placement.matched_against {
+            Some(parent) => format!("\"{}\"", placements[parent].name),
+            None => String::from("null"),
         };
 
-        scanner_locs.push((scanner_location, String::from(&scanner.name)));
-        global_scanner.add_unique_beacons(&scanner, TransformerChain::new(vec![
-            rotate,
-            Translation::translate_to(scanner_location)
-        ]));
+        writeln!(out, "  {{")?;
+        writeln!(out, "    \"name\": \"{}\",", placement.name)?;
+        writeln!(out, "    \"position\": [{}, {}, {}],", position.x, position.y, position.z)?;
+        writeln!(out, "    \"rotation\": [{}],", rows.join(", "))?;
+        writeln!(out, "    \"matched_against\": {},", matched_against)?;
+        writeln!(out, "    \"offset_from_match\": [{}, {}, {}]", offset.x, offset.y, offset.z)?;
+        writeln!(out, "  }}{}", if index + 1 < placements.len() { "," } else { "" })?;
     }
 
-    println!("  Part 1: {} beacons", global_scanner.beacons.len());
+    writeln!(out, "]")?;
+    out.flush()
+}
 
-    let mut max = (0, &String::from("scanner 0"), &String::from("scanner 0"));
-    for (s1, s1name) in scanner_locs.iter() {
-        for (s2, s2name) in scanner_locs.iter() {
-            let len = PointVector::from_subtract(*s1, *s2).len;
-            if len > max.0 {
-                max = (len, s1name, s2name);
-            }
-        }
+// the rotation matrix is flattened row by row into r11..r33
+fn write_csv(placements: &[Placement], filename: &str) -> io::Result<()> {
+    let mut out = BufWriter::new(File::create(filename)?);
+    writeln!(out, "name,x,y,z,r11,r12,r13,r21,r22,r23,r31,r32,r33,matched_against,offset_x,offset_y,offset_z")?;
+
+    for placement in placements {
+        let position = placement.transform.translation;
+        let offset = relative_offset(placements, placement);
+        let cells: Vec<String> = placement.transform.rotation.matrix().iter()
+            .flatten()
+            .map(|cell| cell.to_string())
+            .collect();
+        let matched_against = placement.matched_against
+            .map(|parent| placements[parent].name.clone())
+            .unwrap_or_default();
+
+        writeln!(out, "{},{},{},{},{},{},{},{},{}", placement.name,
+                 position.x, position.y, position.z, cells.join(","), matched_against,
+                 offset.x, offset.y, offset.z)?;
     }
 
-    println!("  Part 2: {} units, between scanner {} and {}", max.0, max.1, max.2);
+    out.flush()
 }
 
-fn both<T>(left: Option<T>, right: Option<T>) -> Option<(T, T)> {
-    match left  { None => None, Some(left) =>
-    match right { None => None, Some(right) =>
-        Some((left, right))
-    }}
+// position of a scanner as seen from the scanner it was matched against
+fn relative_offset(placements: &[Placement], placement: &Placement) -> Point {
+    match placement.matched_against {
+        Some(parent) => placements[parent].transform.inverse()
+            .compose(&placement.transform)
+            .translation,
+        None => Point::ORIGIN,
+    }
 }
 
-fn scanner_location(global_points: &Vec<Point>,
-                    scanner_points: Vec<Point>) -> Option<Point> {
-
-    let opt_1 = global_points[0] - scanner_points[0];
-    let opt_2 = global_points[1] - scanner_points[1];
-    
-    let location = 
-        if opt_1 == opt_2 {
-            opt_1 
-        } else {
-            global_points[0] - scanner_points[1]
-        };
-
-    let alt_1 = global_points[2] - scanner_points[2];
-    let alt_2 = global_points[3] - scanner_points[3];
-
-    let validation = 
-        if alt_1 == alt_2 {
-            alt_1
-        } else {
-            global_points[2] - scanner_points[3]
-        };
-
-    if location == validation {
-        Some(location)
-    } else {
-        None
-    }
+fn describe(transform: &Transform) -> String {
+    let position = transform.translation;
+    format!("at {},{},{} rotated {:?}", position.x, position.y, position.z, transform.rotation.matrix())
 }
 
-fn sort_scanners(global_scanner: &Scanner, scanners: &mut Vec<Scanner>) {
-    for mut scanner in scanners.iter_mut() {
-        scanner.matches_with_world = scanner.compare_to(global_scanner).len();
-    }
+// number of distinct pairs among `beacons`
+fn pairs_between(beacons: usize) -> usize {
+    beacons * (beacons - 1) / 2
+}
 
-    scanners.sort_by(|r, l| {
-        if l.matches_with_world < r.matches_with_world {
-            Ordering::Less
-        } else if l.matches_with_world > r.matches_with_world {
-            Ordering::Greater
-        } else {
-            Ordering::Equal
-        }
-    });
+fn fingerprint(vector: Point) -> Fingerprint {
+    let mut fingerprint = [vector.x.abs(), vector.y.abs(), vector.z.abs()];
+    fingerprint.sort();
+    fingerprint
 }
 
 fn get_input() -> Vec<Scanner> {
-    let input = input::read_all("2021_19")
+    let input = input::read_all(&options::input_name("2021_19"))
         .expect("couldn't get input");
 
-    let groups: Vec<&str> = input.split("\r\n\r\n").collect();
+    let input = input.replace("\r\n", "\n");
+    let groups: Vec<&str> = input.trim().split("\n\n").collect();
 
     let scanners: Vec<Scanner> = groups.iter().map(parse_scanner_group).collect();
 
@@ -446,7 +462,7 @@ fn get_input() -> Vec<Scanner> {
 }
 
 fn parse_scanner_group(group_str: &&str) -> Scanner {
-    let lines: Vec<&str> = group_str.split("\r\n").collect();
+    let lines: Vec<&str> = group_str.split("\n").collect();
     let points: Vec<Vec<i32>> = 
         lines[1..].iter()
             .map(|line| {
@@ -456,13 +472,8 @@ fn parse_scanner_group(group_str: &&str) -> Scanner {
             }).collect();
 
     let beacons: Vec<Point> = points.iter()
-        .map(|arr| {
-            Point {
-                x: arr[0],
-                y: arr[1],
-                z: arr[2],
-            }
-        }).collect();
+        .map(|arr| Point::new(arr[0], arr[1], arr[2]))
+        .collect();
 
-    Scanner::new(lines[0], beacons)
+    Scanner::new(lines[0].trim_matches(|ch| ch == '-' || ch == ' '), beacons)
 }
\ No newline at end of file
@@ -1,134 +1,101 @@
-use crate::input;
-use std::{cmp, collections::VecDeque, ops::{Not, BitAnd, Sub}};
-
-#[derive(Copy, Clone, PartialEq, Debug)]
-struct Cuboid {
-    x_min: i64, x_max: i64,
-    y_min: i64, y_max: i64,
-    z_min: i64, z_max: i64,
-}
+use crate::{input, options};
+use crate::boxes::{self, CuboidSet};
+use std::time::Instant;
+
+type Cuboid = boxes::Cuboid<3>;
+
+const DEFAULT_CLIP: (i64, i64) = (-50, 50);
 
-enum Slice { X(i64), Y(i64), Z(i64) }
 enum Toggle { On, Off }
 struct Instruction(Cuboid, Toggle);
 
-impl Not for Cuboid {
-    type Output = bool;
-
-    // True if this cuboid is invalid, False if it's valid
-    fn not(self) -> Self::Output {
-        self.x_max < self.x_min ||
-        self.y_max < self.y_min ||
-        self.z_max < self.z_min
-    }
+// ways of working out how much of the reactor is on, they should always agree
+#[derive(Copy, Clone, Debug)]
+enum Engine {
+    Split,      /* keeps the on cubes as disjoint cuboids, splitting them as needed */
+    Signed,     /* inclusion-exclusion over signed overlaps */
+    Compressed, /* bitmap over the cells between every cuboid edge */
 }
 
-impl BitAnd for Cuboid {
-    type Output = Self;
-
-    fn bitand(self, other: Self) -> Self::Output {
-        Self {
-            x_min: cmp::max(self.x_min, other.x_min),
-            x_max: cmp::min(self.x_max, other.x_max),
-            y_min: cmp::max(self.y_min, other.y_min),
-            y_max: cmp::min(self.y_max, other.y_max),
-            z_min: cmp::max(self.z_min, other.z_min),
-            z_max: cmp::min(self.z_max, other.z_max),
+// --clip changes the initialization region for part 1, either one range for every axis
+// like --clip=-20..20 or a full cuboid like --clip=x=0..10,y=-5..5,z=0..3
+// --engine=split, signed or compressed picks how volume is counted, --engine=all runs
+// every one of them, times them and checks they agree
+pub fn run() {
+    let region = match options::value("clip") {
+        Some(clip) => parse_clip(&clip),
+        None => Cuboid::inclusive([DEFAULT_CLIP.0; 3], [DEFAULT_CLIP.1; 3]),
+    };
+    let steps: Vec<(Cuboid, bool)> = parse_input().into_iter()
+        .map(|Instruction(cuboid, toggle)| (cuboid, matches!(toggle, Toggle::On)))
+        .collect();
+
+    let engines = match options::value("engine").as_deref() {
+        None | Some("split") => vec![Engine::Split],
+        Some("signed") => vec![Engine::Signed],
+        Some("compressed") => vec![Engine::Compressed],
+        Some("all") => vec![Engine::Split, Engine::Signed, Engine::Compressed],
+        Some(other) => return println!(" unknown engine '{}', expected split, signed, compressed or all", other),
+    };
+
+    let results: Vec<(Engine, (i64, i64))> = engines.iter()
+        .map(|&engine| {
+            let start = Instant::now();
+            let totals = reboot(engine, &steps, &region);
+
+            if engines.len() > 1 {
+                println!(" {:?} engine: {} and {} in {}ms", engine, totals.0, totals.1, start.elapsed().as_millis());
+            }
+            (engine, totals)
+        })
+        .collect();
+
+    let (_, (part_1, part_2)) = results[0];
+    for (engine, totals) in results.iter().skip(1) {
+        if *totals != (part_1, part_2) {
+            println!(" {:?} engine disagrees with {:?}", engine, results[0].0);
         }
     }
-}
-
-impl Sub for Cuboid {
-    type Output = VecDeque<Self>;
-
-    fn sub(self, other: Self) -> Self::Output {
-        let left = self.slice_down(Slice::X(other.x_min - 1));
-        let right = self.slice_up(Slice::X(other.x_max + 1));
-
-        let middle = 
-            self.slice_up(Slice::X(other.x_min))
-                .slice_down(Slice::X(other.x_max));
-
-        let top_middle = middle.slice_up(Slice::Y(other.y_max + 1));
-        let bottom_middle = middle.slice_down(Slice::Y(other.y_min - 1));
-
-        let center =
-            middle.slice_up(Slice::Y(other.y_min))
-                  .slice_down(Slice::Y(other.y_max));
-
-        let center_front = center.slice_down(Slice::Z(other.z_min - 1));
-        let center_back = center.slice_up(Slice::Z(other.z_max + 1));
 
-        let mut slices = VecDeque::from([
-            left, right, top_middle, bottom_middle, center_front, center_back
-        ]);
-        
-        slices.retain(|&cuboid| !!cuboid);
-
-        slices
-    }
+    println!(" Part 1: {}", part_1);
+    println!(" Part 2: {}", part_2);
 }
 
-impl Cuboid {
-    fn slice_down(&self, slice: Slice) -> Self {
-        match slice {
-            Slice::X(at) => Self { x_max: at, ..*self },
-            Slice::Y(at) => Self { y_max: at, ..*self },
-            Slice::Z(at) => Self { z_max: at, ..*self },
-        }
-    }
+// cubes left on inside `region`, and everywhere
+fn reboot(engine: Engine, steps: &[(Cuboid, bool)], region: &Cuboid) -> (i64, i64) {
+    let clipped: Vec<(Cuboid, bool)> = steps.iter()
+        .filter_map(|&(cuboid, on)| cuboid.intersection(region).map(|inside| (inside, on)))
+        .collect();
 
-    fn slice_up(&self, slice: Slice) -> Self {
-        match slice {
-            Slice::X(at) => Self { x_min: at, ..*self },
-            Slice::Y(at) => Self { y_min: at, ..*self },
-            Slice::Z(at) => Self { z_min: at, ..*self },
-        }
-    }
+    match engine {
+        Engine::Split => {
+            let mut reactor: CuboidSet<3> = CuboidSet::new();
+
+            for &(cuboid, on) in steps.iter() {
+                if on { reactor.insert(cuboid) } else { reactor.remove(&cuboid) }
+            }
 
-    fn area(&self) -> i64 {
-        (self.x_max - self.x_min) * (self.y_max - self.y_min) * (self.z_max - self.z_min)
+            (reactor.clip(region).volume(), reactor.volume())
+        },
+        Engine::Signed => (boxes::signed_volume(&clipped), boxes::signed_volume(steps)),
+        Engine::Compressed => (boxes::compressed_volume(&clipped), boxes::compressed_volume(steps)),
     }
 }
 
-pub fn run() {
-    let mut existing_cuboids: VecDeque<Cuboid> = VecDeque::new();
-
-    for Instruction(cuboid, toggle) in parse_input() {
-        let mut new_cuboids: VecDeque<Cuboid> = VecDeque::new();
-
-        existing_cuboids.retain(|&shape| {
-            let overlap = cuboid & shape;
-            if !overlap {
-                true
-            } else {
-                if overlap != shape {
-                    new_cuboids.append(&mut (shape - overlap));
-                }
-                false
-            }
-        });
-        
-        match toggle {
-            Toggle::Off => (),
-            Toggle::On => {
-                new_cuboids.push_back(cuboid);
-            },
-        };
-
-        existing_cuboids.append(&mut new_cuboids);
+fn parse_clip(clip: &str) -> Cuboid {
+    if clip.contains('=') {
+        return parse_cuboid(clip);
     }
 
-    let area = existing_cuboids.iter().fold(0, |acc, c| acc + c.area());
-
-    println!(" Part 2: {}", area);
+    let (min, max) = parse_bounds(clip);
+    Cuboid::inclusive([min; 3], [max; 3])
 }
 
 // input format:
 //   on x=30638..51715,y=35872..46823,z=-56349..-29601
 //   off x=-27831..-17345,y=-19515..-7960,z=76040..92701
 fn parse_input() -> Vec<Instruction> {
-    input::read_lines("2021_22")
+    input::read_lines(&options::input_name("2021_22"))
         .expect("Couldn't read input")
         .iter()
         .map(|line| {
@@ -152,17 +119,19 @@ fn parse_cuboid(input: &str) -> Cuboid {
     let (y_min, y_max) = parse_range(coords[1]);
     let (z_min, z_max) = parse_range(coords[2]);
 
-    Cuboid {
-        x_min, x_max,
-        y_min, y_max,
-        z_min, z_max,
-    }
+    Cuboid::inclusive([x_min, y_min, z_min], [x_max, y_max, z_max])
 }
 
 // input format:
 //   x=-27831..-17345
 fn parse_range(input: &str) -> (i64, i64) {
-    let parts = input[2..].split("..").collect::<Vec<&str>>();
+    parse_bounds(&input[2..])
+}
+
+// input format:
+//   -27831..-17345
+fn parse_bounds(input: &str) -> (i64, i64) {
+    let parts = input.split("..").collect::<Vec<&str>>();
     let min = parts[0];
     let max = parts[1];
 
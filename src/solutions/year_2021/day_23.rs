@@ -1,4 +1,5 @@
-use std::collections::VecDeque;
+use crate::{input, options, pathfinding};
+use std::io::{self, BufRead, Write};
 
 /*
 Plays puzzle game with board like this:
@@ -12,7 +13,8 @@ Plays puzzle game with board like this:
   ║B║C║D║C║
   ╚═╩═╩═╩═╝
 
-array index reference:
+array index reference, the hallway comes first and then each room from the top down.
+for the usual hallway of 11 and rooms 4 deep:
 ╔═════════════════════════════════╗
 ║ 0  1  2  3  4  5  6  7  8  9 10 ║
 ╚════╗ 11 ╔╗ 15 ╔╗ 19 ╔╗ 23 ╔═════╝
@@ -22,6 +24,9 @@ array index reference:
      ╚════╩╩════╩╩════╩╩════╝
 */
 
+// the two rows folded out of the diagram for part 2, inserted below the top row
+const UNFOLDED: [&str; 2] = ["DCBA", "DBAC"];
+
 #[derive(Copy, Clone, PartialEq)]
 enum Pod {
     A, B, C, D,
@@ -38,6 +43,13 @@ enum HomeState {
 use Pod::*;
 use HomeState::*;
 
+// shape of the burrow, generated from the input diagram
+struct Layout {
+    hallway: usize,
+    doors: [usize; 4], /* hallway space right outside each room */
+    depth: usize,
+}
+
 impl Pod {
     fn cost(&self) -> u32 {
         match self {
@@ -66,20 +78,193 @@ impl Pod {
             _ => Some(self),
         }
     }
+
+    // index of the room this pod belongs in
+    fn room(&self) -> Option<usize> {
+        match self {
+            A => Some(0),
+            B => Some(1),
+            C => Some(2),
+            D => Some(3),
+            Empty => None,
+        }
+    }
+
+    fn symbol(&self) -> char {
+        match self {
+            A => 'A',
+            B => 'B',
+            C => 'C',
+            D => 'D',
+            Empty => ' ',
+        }
+    }
+
+    fn from_bits(bits: u128) -> Pod {
+        match bits {
+            1 => A,
+            2 => B,
+            3 => C,
+            4 => D,
+            _ => Empty,
+        }
+    }
+
+    fn from_char(ch: char) -> Option<Pod> {
+        match ch {
+            'A' => Some(A),
+            'B' => Some(B),
+            'C' => Some(C),
+            'D' => Some(D),
+            '.' => Some(Empty),
+            _ => None,
+        }
+    }
+}
+
+impl Layout {
+    fn slot(&self, room: usize, depth: usize) -> usize {
+        self.hallway + room * self.depth + depth
+    }
+
+    // (room, depth) for a space inside a room
+    fn room_of(&self, index: usize) -> Option<(usize, usize)> {
+        if index < self.hallway {
+            None
+        } else {
+            Some(((index - self.hallway) / self.depth, (index - self.hallway) % self.depth))
+        }
+    }
+
+    fn describe(&self, index: usize) -> String {
+        match self.room_of(index) {
+            None => format!("hallway {}", index),
+            Some((room, depth)) => format!("room {} space {}", [A, B, C, D][room].symbol(), depth + 1),
+        }
+    }
+
+    // hallway spaces a pod can stop on, anywhere but right outside a room
+    fn stops(&self) -> Vec<usize> {
+        (0..self.hallway).filter(|space| !self.doors.contains(space)).collect()
+    }
+}
+
+#[derive(Clone)]
+struct Board<'l> {
+    layout: &'l Layout,
+    energy: u32,
+    space: Vec<Pod>,
 }
 
+// one pod moving from space to space, along with what it cost
 #[derive(Copy, Clone)]
-struct Board {
+struct Move {
+    pod: Pod,
+    from: usize,
+    to: usize,
     energy: u32,
-    space: [Pod; 27],
-    a_home: HomeState,
-    b_home: HomeState,
-    c_home: HomeState,
-    d_home: HomeState,
 }
 
-impl Board {
-    fn move_pod(&self, start: usize, end: usize) -> Option<Board> {
+impl<'l> Board<'l> {
+    fn new(layout: &'l Layout, space: Vec<Pod>) -> Board<'l> {
+        if space.len() > 128 / 3 {
+            panic!("burrow has {} spaces, at most {} fit in a board key", space.len(), 128 / 3);
+        }
+
+        for pod in [A, B, C, D] {
+            let count = space.iter().filter(|&&other| other == pod).count();
+            if count != layout.depth {
+                panic!("burrow needs {} of each amphipod, found {}", layout.depth, count);
+            }
+        }
+
+        Board {
+            layout,
+            energy: 0,
+            space,
+        }
+    }
+
+    // draws the board like the diagram at the top of this file
+    fn render(&self) -> String {
+        let layout = self.layout;
+        let width = layout.hallway;
+        let walls: Vec<usize> = layout.doors.iter().flat_map(|&door| [door - 1, door + 1]).collect();
+        let first = walls.iter().min().copied().unwrap();
+        let last = walls.iter().max().copied().unwrap();
+        let shared = |x: usize| layout.doors.contains(&(x - 1)) && layout.doors.contains(&(x + 1));
+
+        let mut out = format!("╔{}╗\n", "═".repeat(width));
+        out += &format!("║e:{:>w$}║\n", self.energy, w = width - 2);
+        out += &format!("╠{}╣\n", "═".repeat(width));
+        out += &format!("║{}║\n", (0..width).map(|i| self.space[i].symbol()).collect::<String>());
+
+        for depth in 0..layout.depth {
+            let row: String = (0..width)
+                .map(|x| match layout.doors.iter().position(|&door| door == x) {
+                    Some(room) => self.space[layout.slot(room, depth)].symbol(),
+                    None if depth == 0 && x == first => '╣',
+                    None if depth == 0 && x == last => '╠',
+                    None if walls.contains(&x) => '║',
+                    None if depth == 0 => '═',
+                    None => ' ',
+                })
+                .collect();
+
+            if depth == 0 {
+                out += &format!("╚{}╝\n", row);
+            } else {
+                out += &format!(" {}\n", row.trim_end());
+            }
+        }
+
+        let bottom: String = (0..width)
+            .map(|x| {
+                if layout.doors.contains(&x) { '═' }
+                else if x == first { '╚' }
+                else if x == last { '╝' }
+                else if walls.contains(&x) && shared(x) { '╩' }
+                else if walls.contains(&x) && layout.doors.contains(&(x - 1)) { '╝' }
+                else if walls.contains(&x) { '╚' }
+                else { ' ' }
+            })
+            .collect();
+        out += &format!(" {}\n", bottom.trim_end());
+
+        out
+    }
+
+    // three bits per space, enough to tell boards apart in the visited map
+    fn key(&self) -> u128 {
+        self.space.iter().rev()
+            .fold(0, |key, pod| key << 3 | pod.room().map_or(0, |room| room as u128 + 1))
+    }
+
+    fn from_key(layout: &'l Layout, key: u128, energy: u32) -> Board<'l> {
+        let spaces = layout.hallway + 4 * layout.depth;
+
+        Board {
+            layout,
+            energy,
+            space: (0..spaces).map(|i| Pod::from_bits(key >> (3 * i) & 0b111)).collect(),
+        }
+    }
+
+    // a room is emptied of every stranger before its own pods move in
+    fn home_state(&self, room: usize) -> HomeState {
+        let owner = [A, B, C, D][room];
+        let pods = (0..self.layout.depth).map(|depth| self.space[self.layout.slot(room, depth)]);
+
+        if pods.clone().all(|pod| pod == owner) {
+            Finished
+        } else if pods.clone().all(|pod| pod == owner || pod.empty()) {
+            Filling
+        } else {
+            Emptying
+        }
+    }
+
+    fn move_pod(&self, start: usize, end: usize) -> Option<Board<'l>> {
         let moving_pod = self.space[start];
         let mut spaces_moved: u32 = 0;
         let mut from: usize = start;
@@ -89,24 +274,20 @@ impl Board {
             return None;
         }
 
-        if from >= 11 { 
+        if let Some((room, depth)) = self.layout.room_of(from) {
             // count spaces to get out of the home
-            let index = from - 11;
-            let col = index / 4;
-            spaces_moved += ((index as u32) % 4) + 1;
-            from = (col + 1) * 2;
+            spaces_moved += depth as u32 + 1;
+            from = self.layout.doors[room];
 
             if self.space[from].occupied() {
                 return None;
             }
         }
 
-        if to >= 11 { 
+        if let Some((room, depth)) = self.layout.room_of(to) {
             // count spaces to get into target spot in home
-            let index = to - 11;
-            let col = index / 4;
-            spaces_moved += ((index as u32) % 4) + 1;
-            to = (col + 1) * 2;
+            spaces_moved += depth as u32 + 1;
+            to = self.layout.doors[room];
         }
 
         while from != to {
@@ -123,59 +304,22 @@ impl Board {
         }
 
         // route is clear, execute swap
-        let mut board = *self;
+        let mut board = self.clone();
 
-        let energy = moving_pod.cost() * (spaces_moved as u32);
+        let energy = moving_pod.cost() * spaces_moved;
         board.energy += energy;
         board.space[end] = moving_pod;
         board.space[start] = Empty;
 
-        match start {
-            14 => if board.space[14] == Empty {
-                board.a_home = Filling;
-            },
-            18 => if board.space[18] == Empty {
-                board.b_home = Filling;
-            },
-            22 => if board.space[22] == Empty {
-                board.c_home = Filling;
-            },
-            26 => if board.space[26] == Empty {
-                board.d_home = Filling;
-            },
-            _ => {}
-        }
-
-        match end {
-            11 => if board.space[11] == A {
-                board.a_home = Finished;
-            },
-            15 => if board.space[15] == B {
-                board.b_home = Finished;
-            },
-            19 => if board.space[19] == C {
-                board.c_home = Finished;
-            },
-            23 => if board.space[23] == D {
-                board.d_home = Finished;
-            },
-            _ => {}
-        }
-
         Some(board)
     }
 
     fn get_home_slot(&self, pod: Pod) -> Option<usize> {
-        let (state, indexes) = match pod {
-            A => (self.a_home, (11..15).rev()),
-            B => (self.b_home, (15..19).rev()),
-            C => (self.c_home, (19..23).rev()),
-            D => (self.d_home, (23..27).rev()),
-            Empty => {return None},
-        };
-        
-        if state == Filling {
-            for i in indexes {
+        let room = pod.room()?;
+
+        if self.home_state(room) == Filling {
+            for depth in (0..self.layout.depth).rev() {
+                let i = self.layout.slot(room, depth);
                 if self.space[i].empty() {
                     return Some(i);
                 }
@@ -185,118 +329,438 @@ impl Board {
         None
     }
 
-    fn move_to_home(&self, start: usize) -> Option<Board> {
+    fn move_to_home(&self, start: usize) -> Option<(Board<'l>, Move)> {
         self.space[start]
             .occupant()
             .and_then(|pod| self.get_home_slot(pod))
-            .and_then(|target| self.move_pod(start, target))
+            .and_then(|target| self.step(start, target))
     }
 
-    fn get_next_to_leave(&self, home_type: Pod) -> Option<usize> {
-        let (state, indexes) = match home_type {
-            A => (self.a_home, 11..15),
-            B => (self.b_home, 15..19),
-            C => (self.c_home, 19..23),
-            D => (self.d_home, 23..27),
-            Empty => {return None},
-        };
+    // the same rules the solver follows, with a reason whenever a move isn't allowed
+    fn check_move(&self, start: usize, end: usize) -> Result<Board<'l>, String> {
+        let spaces = self.space.len();
+        if start >= spaces || end >= spaces {
+            return Err(format!("spaces are numbered 0 to {}", spaces - 1));
+        }
 
-        if state == Emptying {
-            for i in indexes {
-                if self.space[i].occupied() {
-                    return Some(i);
-                }
-            }
+        let pod = self.space[start].occupant()
+            .ok_or(format!("there's no amphipod in {}", self.layout.describe(start)))?;
+        let home = self.get_home_slot(pod);
+
+        match self.layout.room_of(start) {
+            None if home != Some(end) => return Err(format!(
+                "from the hallway {} can only go to the deepest free space of room {}, once no strangers are in it",
+                pod.symbol(), pod.symbol())),
+            Some((room, _)) if self.get_next_to_leave(room) != Some(start) => return Err(String::from(
+                "only the top amphipod can leave a room, and only while a stranger is still in it")),
+            Some(_) if home != Some(end) && !self.layout.stops().contains(&end) => return Err(format!(
+                "{} can stop in the hallway, but not outside a room, or go straight home", pod.symbol())),
+            _ => {},
         }
 
-        None
+        self.move_pod(start, end).ok_or(String::from("something is in the way"))
     }
 
-    fn get_all_possible_moves(mut board: Board) -> VecDeque<Board> {
-        let mut changed = false;
+    // move_pod, keeping track of the move that was made
+    fn step(&self, start: usize, end: usize) -> Option<(Board<'l>, Move)> {
+        let board = self.move_pod(start, end)?;
+        let energy = board.energy - self.energy;
 
-        for i in 0..11 {
-            if let Some(new_board) = board.move_to_home(i) {
-                board = new_board;
-                changed = true;
+        Some((board, Move { pod: self.space[start], from: start, to: end, energy }))
+    }
+
+    fn get_next_to_leave(&self, room: usize) -> Option<usize> {
+        if self.home_state(room) == Emptying {
+            for depth in 0..self.layout.depth {
+                let i = self.layout.slot(room, depth);
+                if self.space[i].occupied() {
+                    return Some(i);
+                }
             }
         }
 
-        for home_type in [A, B, C, D] {
-            let move_attempt = board.get_next_to_leave(home_type)
-                                    .and_then(|start| board.move_to_home(start));
+        None
+    }
 
-            if let Some(new_board) = move_attempt {
-                board = new_board;
-                changed = true;
+    // pods that can get home go straight there, over and over since each one home can clear
+    // the way for another. then the board as it stands, along with every board reachable by
+    // sending one pod out into the hallway, each with the moves that led to it
+    fn get_all_possible_moves(mut board: Board<'l>) -> Vec<(Board<'l>, Vec<Move>)> {
+        let mut forced: Vec<Move> = Vec::new();
+
+        loop {
+            let leaving = (0..4).filter_map(|room| board.get_next_to_leave(room));
+            let home_move = (0..board.layout.hallway).chain(leaving)
+                .find_map(|start| board.move_to_home(start));
+
+            match home_move {
+                Some((new_board, step)) => {
+                    board = new_board;
+                    forced.push(step);
+                },
+                None => break,
             }
         }
 
-        let mut moves = VecDeque::new();
-        
-        for home_type in [A, B, C, D] {
-            if let Some(start) = board.get_next_to_leave(home_type) {
-                for i in [0, 1, 3, 5, 7, 9, 10] {
-                    if let Some(board) = board.move_pod(start, i) {
-                        moves.push_back(board);
+        let mut moves = Vec::new();
+
+        for room in 0..4 {
+            if let Some(start) = board.get_next_to_leave(room) {
+                for i in board.layout.stops() {
+                    if let Some((next, step)) = board.step(start, i) {
+                        if !next.deadlocked() {
+                            let mut steps = forced.clone();
+                            steps.push(step);
+                            moves.push((next, steps));
+                        }
                     }
                 }
             }
         }
 
-        if moves.len() == 0 && changed {
-            moves.push_back(board);
+        if !forced.is_empty() {
+            moves.push((board, forced));
         }
 
         moves
     }
 
     fn finished(&self) -> bool {
-        self.a_home == Finished && self.b_home == Finished && self.c_home == Finished && self.d_home == Finished
+        (0..4).all(|room| self.home_state(room) == Finished)
+    }
+
+    // two pods in the hallway that each need to get past the other can never get home
+    fn deadlocked(&self) -> bool {
+        let door = |pod: Pod| self.layout.doors[pod.room().unwrap()];
+        let hallway: Vec<(usize, Pod)> = (0..self.layout.hallway)
+            .filter_map(|i| self.space[i].occupant().map(|pod| (i, pod)))
+            .collect();
+
+        hallway.iter().enumerate().any(|(n, &(left, left_pod))| {
+            hallway[n + 1..].iter().any(|&(right, right_pod)| {
+                door(left_pod) > right && door(right_pod) < left
+            })
+        })
+    }
+
+    // energy still needed if nothing were ever in the way, so it never overestimates.
+    // every pod not already settled at the bottom of its room walks to its door, and
+    // the pods moving into a room fill it from the bottom, one space shallower each
+    fn lower_bound(&self) -> u32 {
+        let mut energy = 0;
+
+        for room in 0..4 {
+            let owner = [A, B, C, D][room];
+            let settled = (0..self.layout.depth).rev()
+                .take_while(|&depth| self.space[self.layout.slot(room, depth)] == owner)
+                .count();
+            let arriving = (self.layout.depth - settled) as u32;
+
+            energy += owner.cost() * arriving * (arriving + 1) / 2;
+        }
+
+        for (i, pod) in self.space.iter().enumerate() {
+            let target = match pod.room() {
+                Some(room) => room,
+                None => continue,
+            };
+            let door = self.layout.doors[target];
+
+            let steps = match self.layout.room_of(i) {
+                None => i.abs_diff(door),
+                Some((room, depth)) if room != target => depth + 1 + self.layout.doors[room].abs_diff(door),
+                Some((room, depth)) => {
+                    let below = (depth + 1..self.layout.depth).map(|d| self.space[self.layout.slot(room, d)]);
+                    if below.clone().all(|other| other == *pod) { continue; }
+
+                    // has to step out of the way and come back in
+                    depth + 1 + 2
+                },
+            };
+
+            energy += pod.cost() * steps as u32;
+        }
+
+        energy
     }
 }
 
+// --moves prints every move of the cheapest solution along with the board after it
+// --play, or --play=2 for the unfolded burrow, moves the amphipods by hand instead
 pub fn run() {
+    if options::flag("play") || options::value("play").is_some() {
+        let unfold = options::parsed("play", 1) == 2;
+        let (layout, space) = parse_input(unfold);
+        return play(Board::new(&layout, space));
+    }
 
-    /* ideas:
-        1. detect if board in locked state
-        2. Some kind of prioritizing of board states
-    */
-
-    let mut least_energy_used: u32 = /*u32::MAX*/ 150000;
-    let mut board_states: VecDeque<Board> = VecDeque::new();
-    board_states.push_back(starting_board());
-
-    while let Some(next_board) = board_states.pop_front() {
-        if next_board.energy < least_energy_used {
-            for board in Board::get_all_possible_moves(next_board) {
-                if board.finished() {
-                    if board.energy < least_energy_used {
-                        least_energy_used = board.energy;
-                    }
+    for (part, unfold) in [(1, false), (2, true)] {
+        let (layout, space) = parse_input(unfold);
+        let start = Board::new(&layout, space);
+
+        match least_energy(start.clone()) {
+            Some((energy, moves)) => {
+                println!(" Part {}: {} energy in {} moves", part, energy, moves.len());
+                if options::flag("moves") {
+                    print_moves(start, &moves);
+                }
+            },
+            None => println!(" Part {}: the amphipods can't all get home", part),
+        }
+    }
+}
+
+// A* over board states keyed on energy spent, always expanding the board with the lowest
+// energy plus lower bound, so the first finished board popped is the cheapest one
+fn least_energy(start: Board) -> Option<(u32, Vec<Move>)> {
+    let layout = start.layout;
+    let board = |key: &u128| Board::from_key(layout, *key, 0);
+
+    let route = pathfinding::search(
+        start.key(),
+        |key| board(key).finished(),
+        |key| Board::get_all_possible_moves(board(key)).into_iter()
+            .map(|(next, moves)| (next.key(), next.energy, moves))
+            .collect(),
+        |key| board(key).lower_bound(),
+    )?;
+
+    Some((route.cost, route.steps.concat()))
+}
+
+fn play(start: Board) {
+    let mut history: Vec<Board> = vec![start];
+    let stdin = io::stdin();
+    let mut lines = stdin.lock().lines();
+
+    print_play_help(&history[0]);
+
+    loop {
+        let board = history.last().unwrap().clone();
+        print!("{}", board.render());
+
+        if board.finished() {
+            let best = least_energy(history[0].clone()).map_or(0, |(energy, _)| energy);
+            println!(" every amphipod is home, {} energy spent, {} was possible", board.energy, best);
+            return;
+        }
+
+        print!("> ");
+        io::stdout().flush().expect("couldn't write prompt");
+
+        let line = match lines.next() {
+            Some(Ok(line)) => line,
+            _ => return,
+        };
+        let words: Vec<&str> = line.split_whitespace().collect();
+
+        match words.as_slice() {
+            ["quit"] | ["q"] => return,
+            ["help"] | ["?"] => print_play_help(&board),
+            ["undo"] | ["u"] => {
+                if history.len() > 1 {
+                    history.pop();
                 } else {
-                    board_states.push_back(board);
+                    println!(" nothing to undo");
                 }
-            }
+            },
+            ["hint"] | ["h"] => match least_energy(board.clone()) {
+                Some((energy, moves)) => {
+                    let step = moves[0];
+                    println!(" try {} from {} to {}, the best finish from here takes {} more energy",
+                             step.pod.symbol(), board.layout.describe(step.from), board.layout.describe(step.to), energy);
+                },
+                None => println!(" there's no way to finish from here, try undo"),
+            },
+            [from, to] => match (from.parse(), to.parse()) {
+                (Ok(from), Ok(to)) => match board.check_move(from, to) {
+                    Ok(next) => history.push(next),
+                    Err(reason) => println!(" can't move there, {}", reason),
+                },
+                _ => println!(" moves are two space numbers, like 12 3"),
+            },
+            _ => println!(" unknown command, try help"),
+        }
+    }
+}
+
+fn print_play_help(board: &Board) {
+    let layout = board.layout;
+
+    println!(" move an amphipod by giving the space it's on and the space it should go to, like 12 3");
+    println!(" hallway spaces are 0 to {} from the left", layout.hallway - 1);
+    for room in 0..4 {
+        println!(" room {} is {} to {} from the top down", [A, B, C, D][room].symbol(),
+                 layout.slot(room, 0), layout.slot(room, layout.depth - 1));
+    }
+    println!(" other commands: undo, hint, help, quit\n");
+}
+
+fn print_moves(start: Board, moves: &[Move]) {
+    let mut board = start;
+    print!("{}", board.render());
+
+    for step in moves {
+        board = board.move_pod(step.from, step.to).expect("solution made an illegal move");
+        println!("\n {} moves from {} to {} for {} energy", step.pod.symbol(),
+                 board.layout.describe(step.from), board.layout.describe(step.to), step.energy);
+        print!("{}", board.render());
+    }
+}
+
+// input format:
+//   #############
+//   #...........#
+//   ###B#A#A#D###
+//     #B#C#D#C#
+//     #########
+// part 2 unfolds the diagram, putting two more rows under the top one
+fn parse_input(unfold: bool) -> (Layout, Vec<Pod>) {
+    let lines = input::read_lines(&options::input_name("2021_23"))
+        .expect("Couldn't read input");
+
+    parse_diagram(&lines, unfold)
+}
+
+fn parse_diagram(lines: &[String], unfold: bool) -> (Layout, Vec<Pod>) {
+    let hallway: Vec<Pod> = lines[1].trim().trim_matches('#').chars()
+        .map(|ch| Pod::from_char(ch).expect("unknown hallway space"))
+        .collect();
+
+    // hallway space 0 sits one column in from the wall
+    let doors: Vec<usize> = lines[2].char_indices()
+        .filter(|&(_, ch)| ch != '#' && ch != ' ')
+        .map(|(x, _)| x - 1)
+        .collect();
+    if doors.len() != 4 {
+        panic!("burrow needs 4 rooms, found {}", doors.len());
+    }
+
+    let mut rows: Vec<Vec<Pod>> = lines[2..].iter()
+        .map(|line| {
+            doors.iter()
+                .filter_map(|&door| line.chars().nth(door + 1))
+                .filter_map(Pod::from_char)
+                .collect::<Vec<Pod>>()
+        })
+        .filter(|row| row.len() == doors.len())
+        .collect();
+
+    if unfold {
+        for (offset, row) in UNFOLDED.iter().enumerate() {
+            rows.insert(1 + offset, row.chars().filter_map(Pod::from_char).collect());
         }
     }
 
-    println!("All paths explored\n Least energy spent: {} energy", least_energy_used);
+    let layout = Layout {
+        hallway: hallway.len(),
+        doors: [doors[0], doors[1], doors[2], doors[3]],
+        depth: rows.len(),
+    };
+
+    let mut space = hallway;
+    for room in 0..4 {
+        for row in rows.iter() {
+            space.push(row[room]);
+        }
+    }
+
+    (layout, space)
 }
 
-fn starting_board() -> Board {
-    Board {
-        energy: 0,
-        space: [
-            Empty, Empty, Empty, Empty, Empty, Empty, Empty, Empty, Empty, Empty, Empty,
-            B, D, D, B,
-            A, C, B, C,
-            A, B, A, D,
-            D, A, C, C,
-        ],
-        a_home: Emptying,
-        b_home: Emptying,
-        c_home: Emptying,
-        d_home: Emptying,
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // plain Dijkstra where every legal single move is its own step, nothing forced or pruned
+    fn exhaustive(start: Board) -> Option<u32> {
+        let layout = start.layout;
+        let stops = layout.stops();
+
+        let single_moves = |key: &u128| {
+            let board = Board::from_key(layout, *key, 0);
+
+            // only the top pod of a room can get out, and from the hallway the only way is
+            // home, so there's no point asking check_move about anything else
+            let tops = (0..4).filter_map(|room| {
+                (0..layout.depth).map(|depth| layout.slot(room, depth)).find(|&i| board.space[i].occupied())
+            });
+            let mut moves = Vec::new();
+
+            for from in (0..layout.hallway).filter(|&i| board.space[i].occupied()).chain(tops) {
+                let home = board.get_home_slot(board.space[from]);
+                let hallway = if layout.room_of(from).is_some() { &stops[..] } else { &[] };
+
+                for &to in hallway.iter().chain(home.iter()) {
+                    if let Ok(next) = board.check_move(from, to) {
+                        moves.push((next.key(), next.energy, ()));
+                    }
+                }
+            }
+
+            moves
+        };
+
+        pathfinding::search(start.key(), |key| Board::from_key(layout, *key, 0).finished(), single_moves, |_| 0)
+            .map(|route| route.cost)
+    }
+
+    fn diagram(top: &str, bottom: &str) -> Vec<String> {
+        ["#############", "#...........#", top, bottom, "  #########"]
+            .iter().map(|line| line.to_string()).collect()
+    }
+
+    // rooms filled with two of each amphipod, shuffled the same way on every run
+    fn burrows(count: usize) -> Vec<(String, String)> {
+        let mut pods: Vec<char> = "AABBCCDD".chars().collect();
+        let mut seed: u64 = 0x2021_0023;
+        let mut burrows = Vec::new();
+
+        while burrows.len() < count {
+            for i in (1..pods.len()).rev() {
+                seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+                pods.swap(i, (seed >> 33) as usize % (i + 1));
+            }
+
+            let row = |pods: &[char]| pods.iter().map(|pod| pod.to_string()).collect::<Vec<String>>().join("#");
+            burrows.push((format!("###{}###", row(&pods[..4])), format!("  #{}#", row(&pods[4..]))));
+        }
+
+        burrows
+    }
+
+    fn check(top: &str, bottom: &str, unfold: bool) {
+        let (layout, space) = parse_diagram(&diagram(top, bottom), unfold);
+        let start = Board::new(&layout, space);
+        let solved = least_energy(start.clone()).map(|(energy, _)| energy);
+
+        assert_eq!(solved, exhaustive(start), "{} {} unfolded: {}", top, bottom, unfold);
+    }
+
+    #[test]
+    fn matches_exhaustive_search_on_the_example() {
+        check("###B#C#B#D###", "  #A#D#C#A#", false);
+    }
+
+    // needs a pod sent home partway through the forced moves before any hallway move
+    #[test]
+    fn matches_exhaustive_search_when_one_pod_home_frees_another() {
+        check("###C#D#D#C###", "  #A#B#B#A#", true);
+    }
+
+    #[test]
+    fn matches_exhaustive_search_on_random_burrows() {
+        for (top, bottom) in burrows(2) {
+            check(&top, &bottom, false);
+        }
+    }
+
+    // the exhaustive search is slow on unfolded burrows, run with --ignored
+    #[test]
+    #[ignore]
+    fn matches_exhaustive_search_on_random_unfolded_burrows() {
+        for (top, bottom) in burrows(1) {
+            check(&top, &bottom, true);
+        }
     }
 }
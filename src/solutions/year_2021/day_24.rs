@@ -1,6 +1,14 @@
-use crate::input;
+use crate::{input, options};
+use std::collections::HashSet;
 use std::fmt::{Display,Formatter,Result};
 
+// every MONAD block is this, apart from the three numbers in the {} slots
+const BLOCK: [&str; 18] = [
+    "inp w", "mul x 0", "add x z", "mod x 26", "div z {}", "add x {}",
+    "eql x w", "eql x 0", "mul y 0", "add y 25", "mul y x", "add y 1",
+    "mul z y", "mul y 0", "add y w", "add y {}", "mul y x", "add z y",
+];
+
 #[derive(Debug, Copy, Clone)]
 enum Symbol {
     W, X, Y, Z,
@@ -23,6 +31,59 @@ struct ArithmeticLogicUnit<'a> {
     program: &'a Vec<Instruction>,
 }
 
+// the numbers that set one MONAD block apart from the others
+#[derive(Debug, Copy, Clone)]
+struct Block {
+    divisor: i64, /* 1 pushes a base 26 digit onto z, 26 pops one off */
+    check: i64,   /* added to the popped digit before comparing it with the input */
+    offset: i64,  /* added to the input before pushing it */
+}
+
+// digit `later` has to equal digit `earlier` plus `offset` for z to end up at zero
+#[derive(Debug, Copy, Clone)]
+struct Constraint {
+    earlier: usize,
+    later: usize,
+    offset: i64,
+}
+
+#[derive(Debug)]
+enum AnalysisError {
+    Shape {                                          /* not a MONAD block */
+        block: usize, line: usize, found: String, expected: String,
+    },
+    Divisor { block: usize, divisor: i64 },          /* z is only ever divided by 1 or 26 */
+    AlwaysPushes { block: usize },                   /* a push block whose check could match a digit */
+    OffsetOutOfRange { block: usize, offset: i64 },  /* a pushed digit wouldn't fit in base 26 */
+    PopOffset { block: usize, offset: i64 },         /* a failed pop could push a zero digit */
+    Unbalanced,                                      /* pops and pushes don't pair up */
+    Unsatisfiable(Constraint),                       /* two digits can never be that far apart */
+}
+
+// instructions the puzzle leaves undefined, a program that hits one never accepts its input
+#[derive(Debug, Copy, Clone)]
+enum Crash {
+    DivideByZero(Instruction),
+    InvalidModulo(Instruction), /* mod with a negative value or a divisor that isn't positive */
+    Overflow(Instruction),
+}
+
+// a range of values a register might hold, i64::MIN and i64::MAX standing in for no limit
+type Interval = (i64, i64);
+
+// finds accepted inputs for any ALU program one input at a time, remembering the register
+// states that led nowhere
+struct Search<'p> {
+    alu: ArithmeticLogicUnit<'p>,
+    start: Option<[i64; 4]>,           /* registers once anything before the first inp has run */
+    segments: Vec<&'p [Instruction]>,  /* each starts with an inp and runs up to the next */
+    live: Vec<[bool; 4]>,              /* registers still read before they're overwritten */
+    reach: Vec<Option<i64>>,           /* z at or above this can't get back down to zero */
+    digits: Vec<i64>,                  /* tried in this order, so the first accepted input wins */
+    failed: HashSet<(usize, [i64; 4])>,
+    states: usize,
+}
+
 use Symbol::*;
 use Instruction::*;
 
@@ -66,6 +127,13 @@ impl Instruction {
             _ => { panic!("unexpected command"); },
         }
     }
+
+    // the register an instruction writes to
+    fn target(&self) -> Symbol {
+        match *self {
+            Inp(sym) | Add(sym, _) | Mul(sym, _) | Div(sym, _) | Mod(sym, _) | Eql(sym, _) => sym,
+        }
+    }
 }
 
 impl Display for Instruction {
@@ -93,7 +161,7 @@ impl ArithmeticLogicUnit<'_> {
         self.registers = [0; 4];
     }
 
-    fn run_program(&mut self, input_stream: [i64; 14], debug: bool) -> i64 {
+    fn run_program(&mut self, input_stream: &[i64], debug: bool) -> std::result::Result<i64, Crash> {
         self.reset();
 
         if debug {
@@ -106,16 +174,7 @@ impl ArithmeticLogicUnit<'_> {
         let mut i = 1;
 
         for &instruction in program {
-            match instruction {
-                Inp(sym) => self.set_value(sym, *input.next().unwrap()),
-                Add(sym1, sym2) => self.set_value(sym1, self.lookup_value(sym1) + self.lookup_value(sym2)),
-                Mul(sym1, sym2) => self.set_value(sym1, self.lookup_value(sym1) * self.lookup_value(sym2)),
-                Div(sym1, sym2) => self.set_value(sym1, self.lookup_value(sym1) / self.lookup_value(sym2)),
-                Mod(sym1, sym2) => self.set_value(sym1, self.lookup_value(sym1) % self.lookup_value(sym2)),
-                Eql(sym1, sym2) => self.set_value(sym1, 
-                    if self.lookup_value(sym1) == self.lookup_value(sym2) { 1 } else { 0 }
-                ),
-            }
+            self.execute(instruction, &mut input)?;
 
             if debug {
                 if let Inp(_) = instruction {
@@ -127,7 +186,37 @@ impl ArithmeticLogicUnit<'_> {
             }
         }
 
-        self.lookup_value(Z)
+        Ok(self.lookup_value(Z))
+    }
+
+    // runs from wherever the registers are now, feeding `digit` to the segment's one inp
+    fn run_segment(&mut self, segment: &[Instruction], digit: i64) -> std::result::Result<(), Crash> {
+        let input = [digit];
+        let mut input = input.iter();
+
+        for &instruction in segment {
+            self.execute(instruction, &mut input)?;
+        }
+
+        Ok(())
+    }
+
+    fn execute<'i>(&mut self, instruction: Instruction, input: &mut impl Iterator<Item = &'i i64>) -> std::result::Result<(), Crash> {
+        let value = match instruction {
+            Inp(_) => Some(*input.next().expect("ran out of input")),
+            Add(sym1, sym2) => self.lookup_value(sym1).checked_add(self.lookup_value(sym2)),
+            Mul(sym1, sym2) => self.lookup_value(sym1).checked_mul(self.lookup_value(sym2)),
+            Div(_, sym2) if self.lookup_value(sym2) == 0 => return Err(Crash::DivideByZero(instruction)),
+            Div(sym1, sym2) => self.lookup_value(sym1).checked_div(self.lookup_value(sym2)),
+            Mod(sym1, sym2) if self.lookup_value(sym1) < 0 || self.lookup_value(sym2) <= 0 =>
+                return Err(Crash::InvalidModulo(instruction)),
+            Mod(sym1, sym2) => Some(self.lookup_value(sym1) % self.lookup_value(sym2)),
+            Eql(sym1, sym2) => Some(if self.lookup_value(sym1) == self.lookup_value(sym2) { 1 } else { 0 }),
+        };
+
+        self.set_value(instruction.target(), value.ok_or(Crash::Overflow(instruction))?);
+
+        Ok(())
     }
 
     fn lookup_value(&self, sym: Symbol) -> i64 {
@@ -142,37 +231,323 @@ impl ArithmeticLogicUnit<'_> {
     }
 }
 
-pub fn run() {
-    println!("2021:24");
+impl Display for Crash {
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        match self {
+            Crash::DivideByZero(instruction) => write!(f, "`{}` divides by zero", instruction),
+            Crash::InvalidModulo(instruction) => write!(f, "`{}` takes an invalid modulo", instruction),
+            Crash::Overflow(instruction) => write!(f, "`{}` overflows", instruction),
+        }
+    }
+}
 
-    let program = read_program();
-    let mut alu = ArithmeticLogicUnit::new(&program);
+impl Display for AnalysisError {
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        match self {
+            AnalysisError::Shape { block, line, found, expected } =>
+                write!(f, "block {} line {} is `{}`, expected `{}`", block + 1, line + 1, found, expected),
+            AnalysisError::Divisor { block, divisor } =>
+                write!(f, "block {} divides z by {}, expected 1 or 26", block + 1, divisor),
+            AnalysisError::AlwaysPushes { block } =>
+                write!(f, "block {} pushes but its check could match an input digit", block + 1),
+            AnalysisError::OffsetOutOfRange { block, offset } =>
+                write!(f, "block {} pushes its input plus {}, which doesn't fit in one base 26 digit", block + 1, offset),
+            AnalysisError::PopOffset { block, offset } =>
+                write!(f, "block {} pushes its input plus {} when its check fails, which could be zero", block + 1, offset),
+            AnalysisError::Unbalanced =>
+                write!(f, "the blocks don't push and pop the same number of digits"),
+            AnalysisError::Unsatisfiable(c) =>
+                write!(f, "digit {} would have to be digit {} {:+}", c.later + 1, c.earlier + 1, c.offset),
+        }
+    }
+}
+
+impl std::error::Error for AnalysisError {}
+
+// splits the program at each inp and pulls the three numbers out of every block
+fn blocks(program: &[Instruction]) -> std::result::Result<Vec<Block>, AnalysisError> {
+    program.chunks(BLOCK.len()).enumerate()
+        .map(|(block, instructions)| {
+            let mut numbers = Vec::new();
+
+            for (line, expected) in BLOCK.iter().enumerate() {
+                let found = instructions.get(line).map_or(String::from("end of program"), |i| i.to_string());
+                let shape_error = || AnalysisError::Shape {
+                    block, line, found: found.clone(), expected: expected.to_string(),
+                };
+
+                match expected.strip_suffix("{}") {
+                    None if found == *expected => {},
+                    Some(prefix) if found.starts_with(prefix) =>
+                        numbers.push(found[prefix.len()..].parse::<i64>().map_err(|_| shape_error())?),
+                    _ => return Err(shape_error()),
+                }
+            }
+
+            Ok(Block { divisor: numbers[0], check: numbers[1], offset: numbers[2] })
+        })
+        .collect()
+}
+
+// z works as a stack of base 26 digits. a block dividing by 1 always pushes its input plus
+// offset, since a check above 9 can't match any digit. a block dividing by 26 pops the top
+// and only avoids pushing again if its input equals what was popped plus its check, which
+// ties its digit to the digit that did the pushing
+fn constraints(blocks: &[Block]) -> std::result::Result<Vec<Constraint>, AnalysisError> {
+    let mut stack: Vec<(usize, i64)> = Vec::new();
+    let mut constraints = Vec::new();
+
+    for (index, block) in blocks.iter().enumerate() {
+        if block.divisor == 1 {
+            // z % 26 + check has to miss every digit from 1 to 9
+            if (-24..=9).contains(&block.check) {
+                return Err(AnalysisError::AlwaysPushes { block: index });
+            }
+            // any input from 1 to 9 plus the offset has to land between 0 and 25
+            if !(-1..=16).contains(&block.offset) {
+                return Err(AnalysisError::OffsetOutOfRange { block: index, offset: block.offset });
+            }
+            stack.push((index, block.offset));
+        } else if block.divisor == 26 {
+            // when the check fails the block pushes input plus offset instead, and that has to
+            // stay above 0 or a failed pop could still leave z at 0
+            if block.offset < 0 {
+                return Err(AnalysisError::PopOffset { block: index, offset: block.offset });
+            }
+            let (earlier, pushed) = stack.pop().ok_or(AnalysisError::Unbalanced)?;
+            let constraint = Constraint { earlier, later: index, offset: pushed + block.check };
 
-    let min_model_number = 11912814611156i64;
-    let max_model_number = 45989929946199i64;
+            if constraint.offset.abs() > 8 {
+                return Err(AnalysisError::Unsatisfiable(constraint));
+            }
+            constraints.push(constraint);
+        } else {
+            return Err(AnalysisError::Divisor { block: index, divisor: block.divisor });
+        }
+    }
 
-    println!("min: {} validation -> {}",
-        min_model_number,
-        alu.run_program(create_input_stream(min_model_number), false));
+    if stack.is_empty() { Ok(constraints) } else { Err(AnalysisError::Unbalanced) }
+}
 
-    println!("max: {} validation -> {}",
-        max_model_number,
-        alu.run_program(create_input_stream(max_model_number), false));
+// pushes each constrained pair of digits as high, or as low, as it will go. every digit
+// belongs to exactly one pair, so there are twice as many digits as constraints
+fn model_number(constraints: &[Constraint], largest: bool) -> Vec<i64> {
+    let mut number = vec![0i64; constraints.len() * 2];
+
+    for c in constraints {
+        let (earlier, later) = match (largest, c.offset >= 0) {
+            (true, true) => (9 - c.offset, 9),
+            (true, false) => (9, 9 + c.offset),
+            (false, true) => (1, 1 + c.offset),
+            (false, false) => (1 - c.offset, 1),
+        };
+
+        number[c.earlier] = earlier;
+        number[c.later] = later;
+    }
+
+    number
 }
 
-fn create_input_stream(mut input_num: i64) -> [i64; 14] {
-    let mut input_stream = [0i64; 14];
+impl<'p> Search<'p> {
+    fn new(program: &'p Vec<Instruction>, largest: bool) -> Search<'p> {
+        let first_input = program.iter().position(|i| matches!(i, Inp(_))).unwrap_or(program.len());
+        let mut alu = ArithmeticLogicUnit::new(program);
+        let mut no_input = [].iter();
+
+        // a crash before any input is read means nothing is accepted
+        let start = program[..first_input].iter()
+            .try_for_each(|&instruction| alu.execute(instruction, &mut no_input))
+            .ok()
+            .map(|_| alu.registers);
+
+        let mut segments = Vec::new();
+        let mut rest = &program[first_input..];
+        while !rest.is_empty() {
+            let end = rest.iter().skip(1).position(|i| matches!(i, Inp(_))).map_or(rest.len(), |end| end + 1);
+            segments.push(&rest[..end]);
+            rest = &rest[end..];
+        }
 
-    for i in (0..14).rev() {
-        input_stream[i] = input_num % 10;
-        input_num = input_num / 10;
+        let mut live = vec![[false; 4]; segments.len() + 1];
+        live[segments.len()][Z.ordinal()] = true;
+        for index in (0..segments.len()).rev() {
+            live[index] = live_before(segments[index], live[index + 1]);
+        }
+
+        let mut reach: Vec<Option<i64>> = vec![Some(1); segments.len() + 1];
+        for index in (0..segments.len()).rev() {
+            reach[index] = shrinks_by(segments[index])
+                .and_then(|divisor| reach[index + 1].map(|reach| reach.saturating_mul(divisor)));
+        }
+
+        Search {
+            alu, start, segments, live, reach,
+            digits: if largest { (1..=9).rev().collect() } else { (1..=9).collect() },
+            failed: HashSet::new(),
+            states: 0,
+        }
     }
 
-    input_stream
+    fn first_accepted(&mut self) -> Option<Vec<i64>> {
+        self.accepted_from(0, self.start?)
+    }
+
+    // the digits for this segment onwards that leave z at zero, if there are any
+    fn accepted_from(&mut self, index: usize, mut registers: [i64; 4]) -> Option<Vec<i64>> {
+        // registers the rest of the program never reads can't change the outcome
+        for (register, live) in registers.iter_mut().zip(self.live[index]) {
+            if !live { *register = 0; }
+        }
+
+        let z = registers[Z.ordinal()];
+        if index == self.segments.len() {
+            return if z == 0 { Some(Vec::new()) } else { None };
+        }
+        if self.reach[index].is_some_and(|reach| z >= reach) || self.failed.contains(&(index, registers)) {
+            return None;
+        }
+        self.states += 1;
+
+        for position in 0..self.digits.len() {
+            let digit = self.digits[position];
+            self.alu.registers = registers;
+            if self.alu.run_segment(self.segments[index], digit).is_err() {
+                continue;
+            }
+
+            if let Some(mut rest) = self.accepted_from(index + 1, self.alu.registers) {
+                rest.insert(0, digit);
+                return Some(rest);
+            }
+        }
+
+        self.failed.insert((index, registers));
+        None
+    }
+}
+
+// which registers are read before being written, going into `segment`, given which are
+// read after it. `mul r 0` only ever writes r, which is how MONAD clears x and y
+fn live_before(segment: &[Instruction], mut live: [bool; 4]) -> [bool; 4] {
+    for &instruction in segment.iter().rev() {
+        match instruction {
+            Inp(sym) | Mul(sym, Scaler(0)) => live[sym.ordinal()] = false,
+            Add(sym1, sym2) | Mul(sym1, sym2) | Div(sym1, sym2) | Mod(sym1, sym2) | Eql(sym1, sym2) => {
+                live[sym1.ordinal()] = true;
+                if let Scaler(_) = sym2 {} else { live[sym2.ordinal()] = true; }
+            },
+        }
+    }
+
+    live
+}
+
+// how far a segment can bring a non-negative z down: the product of the constants it
+// divides z by, as long as every other change to z can only keep it where it is or raise
+// it. anything else, like subtracting from z or taking it mod something, gives None
+fn shrinks_by(segment: &[Instruction]) -> Option<i64> {
+    const ANY: Interval = (i64::MIN, i64::MAX);
+    let mut ranges = [ANY, ANY, ANY, (0, i64::MAX)];
+    let mut divisor: i64 = 1;
+
+    let range = |ranges: &[Interval; 4], sym: Symbol| match sym {
+        Scaler(val) => (val, val),
+        _ => ranges[sym.ordinal()],
+    };
+
+    for &instruction in segment {
+        match instruction {
+            Div(Z, Scaler(val)) if val > 0 => divisor = divisor.saturating_mul(val),
+            Inp(Z) | Mod(Z, _) | Eql(Z, _) | Div(Z, _) => return None,
+            Add(Z, sym) if range(&ranges, sym).0 < 0 => return None,
+            Mul(Z, sym) if range(&ranges, sym).0 < 1 => return None,
+            _ => {},
+        }
+
+        let (target, value) = match instruction {
+            Inp(sym) => (sym, (1, 9)),
+            Add(sym1, sym2) => {
+                let (a, b) = (range(&ranges, sym1), range(&ranges, sym2));
+                (sym1, (a.0.saturating_add(b.0), a.1.saturating_add(b.1)))
+            },
+            Mul(sym1, sym2) => {
+                let (a, b) = (range(&ranges, sym1), range(&ranges, sym2));
+                let products = [a.0.saturating_mul(b.0), a.0.saturating_mul(b.1), a.1.saturating_mul(b.0), a.1.saturating_mul(b.1)];
+                (sym1, (*products.iter().min().unwrap(), *products.iter().max().unwrap()))
+            },
+            Div(sym1, Scaler(val)) if val > 0 => {
+                let a = range(&ranges, sym1);
+                (sym1, (a.0 / val, a.1 / val))
+            },
+            Mod(sym1, Scaler(val)) if val > 0 && range(&ranges, sym1).0 >= 0 => (sym1, (0, val - 1)),
+            Eql(sym1, _) => (sym1, (0, 1)),
+            Div(sym1, _) | Mod(sym1, _) => (sym1, ANY),
+        };
+        ranges[target.ordinal()] = value;
+    }
+
+    Some(divisor)
+}
+
+// --solver=search skips looking for the MONAD blocks and searches the input space straight
+// away, which also happens whenever the program doesn't fit the pattern
+// --find=min or --find=max only looks for the smallest or largest accepted input
+// --constraints lists how the digits of a valid model number depend on each other
+pub fn run() {
+    println!("2021:24");
+
+    let program = read_program();
+    let mut alu = ArithmeticLogicUnit::new(&program);
+
+    let constraints = match options::value("solver").as_deref() {
+        None | Some("analyze") => match blocks(&program).and_then(|blocks| constraints(&blocks)) {
+            Ok(constraints) => Some(constraints),
+            Err(error) => {
+                println!("not a MONAD program, {}, searching instead", error);
+                None
+            },
+        },
+        Some("search") => None,
+        Some(other) => return println!("unknown solver '{}', expected analyze or search", other),
+    };
+
+    if let (Some(constraints), true) = (&constraints, options::flag("constraints")) {
+        for c in constraints.iter() {
+            println!("digit {:2} = digit {:2} {:+}", c.later + 1, c.earlier + 1, c.offset);
+        }
+    }
+
+    let wanted = match options::value("find").as_deref() {
+        None => vec![("min", false), ("max", true)],
+        Some("min") => vec![("min", false)],
+        Some("max") => vec![("max", true)],
+        Some(other) => return println!("unknown --find '{}', expected min or max", other),
+    };
+
+    for (name, largest) in wanted {
+        let found = match &constraints {
+            Some(constraints) => Some(model_number(constraints, largest)),
+            None => {
+                let mut search = Search::new(&program, largest);
+                let found = search.first_accepted();
+                println!("{}: searched {} states", name, search.states);
+                found
+            },
+        };
+
+        match found {
+            Some(digits) => println!("{}: {} validation -> {}",
+                name,
+                digits.iter().map(|digit| digit.to_string()).collect::<String>(),
+                alu.run_program(&digits, false).map_or_else(|crash| format!("crashed, {}", crash), |z| z.to_string())),
+            None => println!("{}: no input is accepted", name),
+        }
+    }
 }
 
 fn read_program() -> Vec<Instruction> {
-    input::read_lines("2021_24_14")
+    input::read_lines(&options::input_name("2021_24_14"))
         .expect("couldn't load program file")
         .iter()
         .map(|line| Instruction::new(line.split(" ").collect::<Vec<&str>>()))